@@ -0,0 +1,3 @@
+ALTER TABLE video_shares DROP CONSTRAINT IF EXISTS video_shares_video_id_user_id_key;
+
+ALTER TABLE videos DROP COLUMN IF EXISTS visibility;
@@ -0,0 +1,4 @@
+ALTER TABLE videos ADD COLUMN visibility TEXT NOT NULL DEFAULT 'private'
+    CHECK (visibility IN ('private', 'unlisted', 'internal', 'public'));
+
+ALTER TABLE video_shares ADD CONSTRAINT video_shares_video_id_user_id_key UNIQUE (video_id, user_id);
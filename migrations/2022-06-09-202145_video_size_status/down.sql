@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS video_file_size_backfill;
+DROP INDEX IF EXISTS video_shares_user_id_idx;
+DROP INDEX IF EXISTS videos_owner_id_idx;
+
+ALTER TABLE videos DROP COLUMN IF EXISTS status;
+ALTER TABLE videos DROP COLUMN IF EXISTS file_size;
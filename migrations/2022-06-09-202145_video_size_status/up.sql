@@ -0,0 +1,13 @@
+ALTER TABLE videos ADD COLUMN file_size BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE videos ADD COLUMN status TEXT NOT NULL DEFAULT 'ready'
+    CHECK (status IN ('processing', 'ready', 'failed'));
+
+CREATE INDEX videos_owner_id_idx ON videos (owner_id);
+CREATE INDEX video_shares_user_id_idx ON video_shares (user_id);
+
+-- Existing videos get their size from their file on the next startup
+-- (see `video::sql::backfill_video_file_sizes`).
+CREATE TABLE video_file_size_backfill (
+    video_id INTEGER PRIMARY KEY REFERENCES videos(id) ON DELETE CASCADE
+);
+INSERT INTO video_file_size_backfill (video_id) SELECT id FROM videos;
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS videos_search_vector_idx;
+DROP TRIGGER IF EXISTS videos_search_vector_update ON videos;
+DROP FUNCTION IF EXISTS videos_search_vector_update();
+DROP FUNCTION IF EXISTS video_search_document(videos);
+
+ALTER TABLE videos DROP COLUMN IF EXISTS search_vector;
@@ -0,0 +1,23 @@
+ALTER TABLE videos ADD COLUMN search_vector tsvector NOT NULL DEFAULT ''::tsvector;
+
+-- Builds the searchable document for a video. Replaced by later migrations as
+-- more searchable content (tags, captions) is added.
+CREATE FUNCTION video_search_document(video videos) RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', coalesce(video.video_name, '')), 'A') ||
+           setweight(to_tsvector('english', coalesce(video.video_desc, '')), 'B');
+$$ LANGUAGE sql STABLE;
+
+CREATE FUNCTION videos_search_vector_update() RETURNS trigger AS $$
+BEGIN
+    NEW.search_vector := video_search_document(NEW);
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER videos_search_vector_update
+    BEFORE INSERT OR UPDATE OF video_name, video_desc ON videos
+    FOR EACH ROW EXECUTE FUNCTION videos_search_vector_update();
+
+UPDATE videos SET search_vector = video_search_document(videos);
+
+CREATE INDEX videos_search_vector_idx ON videos USING GIN (search_vector);
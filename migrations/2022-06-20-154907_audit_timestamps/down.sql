@@ -0,0 +1,15 @@
+DROP INDEX IF EXISTS videos_created_at_idx;
+DROP TABLE IF EXISTS video_timestamp_backfill;
+
+DROP TRIGGER IF EXISTS users_set_updated_at ON users;
+DROP TRIGGER IF EXISTS videos_set_updated_at ON videos;
+DROP FUNCTION IF EXISTS set_updated_at();
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS last_login_at,
+    DROP COLUMN IF EXISTS updated_at,
+    DROP COLUMN IF EXISTS created_at;
+
+ALTER TABLE videos
+    DROP COLUMN IF EXISTS updated_at,
+    DROP COLUMN IF EXISTS created_at;
@@ -0,0 +1,34 @@
+ALTER TABLE videos
+    ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+ALTER TABLE users
+    ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN last_login_at TIMESTAMP;
+
+-- Bumps updated_at on every update unless the update sets it explicitly
+CREATE FUNCTION set_updated_at() RETURNS trigger AS $$
+BEGIN
+    IF NEW.updated_at IS NOT DISTINCT FROM OLD.updated_at THEN
+        NEW.updated_at := CURRENT_TIMESTAMP;
+    END IF;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER videos_set_updated_at BEFORE UPDATE ON videos
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+-- Logging in is not a change to the user
+CREATE TRIGGER users_set_updated_at BEFORE UPDATE ON users
+    FOR EACH ROW WHEN (OLD.last_login_at IS NOT DISTINCT FROM NEW.last_login_at)
+    EXECUTE FUNCTION set_updated_at();
+
+-- Existing videos get their timestamps from the file modification time on the
+-- next startup (see `video::sql::backfill_video_timestamps`).
+CREATE TABLE video_timestamp_backfill (
+    video_id INTEGER PRIMARY KEY REFERENCES videos(id) ON DELETE CASCADE
+);
+INSERT INTO video_timestamp_backfill (video_id) SELECT id FROM videos;
+
+CREATE INDEX videos_created_at_idx ON videos (created_at);
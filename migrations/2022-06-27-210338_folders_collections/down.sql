@@ -0,0 +1,11 @@
+DROP FUNCTION IF EXISTS video_shared_with(INTEGER, INTEGER);
+DROP FUNCTION IF EXISTS folder_shared_with(INTEGER, INTEGER);
+
+DROP TABLE IF EXISTS collection_videos;
+DROP TABLE IF EXISTS collections;
+DROP TABLE IF EXISTS folder_shares;
+
+DROP INDEX IF EXISTS videos_folder_id_idx;
+ALTER TABLE videos DROP COLUMN IF EXISTS folder_id;
+
+DROP TABLE IF EXISTS folders;
@@ -0,0 +1,69 @@
+CREATE TABLE folders (
+    id SERIAL PRIMARY KEY,
+    folder_id TEXT UNIQUE NOT NULL,
+    owner_id INTEGER NOT NULL REFERENCES users(id),
+    parent_id INTEGER REFERENCES folders(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX folders_owner_id_idx ON folders (owner_id);
+CREATE INDEX folders_parent_id_idx ON folders (parent_id);
+
+CREATE TRIGGER folders_set_updated_at BEFORE UPDATE ON folders
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+ALTER TABLE videos ADD COLUMN folder_id INTEGER REFERENCES folders(id) ON DELETE SET NULL;
+
+CREATE INDEX videos_folder_id_idx ON videos (folder_id);
+
+CREATE TABLE folder_shares (
+    id SERIAL PRIMARY KEY,
+    folder_id INTEGER NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    UNIQUE (folder_id, user_id)
+);
+
+CREATE TABLE collections (
+    id SERIAL PRIMARY KEY,
+    collection_id TEXT UNIQUE NOT NULL,
+    owner_id INTEGER NOT NULL REFERENCES users(id),
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER collections_set_updated_at BEFORE UPDATE ON collections
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE collection_videos (
+    id SERIAL PRIMARY KEY,
+    collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    video_id INTEGER NOT NULL REFERENCES videos(id) ON DELETE CASCADE,
+    UNIQUE (collection_id, video_id)
+);
+
+-- Whether a folder, or any folder above it, is shared with a user
+CREATE FUNCTION folder_shared_with(folder INTEGER, viewer INTEGER) RETURNS boolean AS $$
+    WITH RECURSIVE ancestors (id, parent_id) AS (
+        SELECT id, parent_id FROM folders WHERE id = folder
+        UNION
+        SELECT f.id, f.parent_id FROM folders f JOIN ancestors a ON f.id = a.parent_id
+    )
+    SELECT EXISTS (
+        SELECT 1 FROM folder_shares s JOIN ancestors a ON s.folder_id = a.id
+        WHERE s.user_id = viewer
+    );
+$$ LANGUAGE sql STABLE;
+
+-- Whether a video is shared with a user, either directly or through its folders
+CREATE FUNCTION video_shared_with(video INTEGER, viewer INTEGER) RETURNS boolean AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM video_shares s WHERE s.video_id = video AND s.user_id = viewer
+    ) OR EXISTS (
+        SELECT 1 FROM videos v
+        WHERE v.id = video AND v.folder_id IS NOT NULL AND folder_shared_with(v.folder_id, viewer)
+    );
+$$ LANGUAGE sql STABLE;
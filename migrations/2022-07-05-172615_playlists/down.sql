@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS one_time_playlist;
+DROP TABLE IF EXISTS playlist_shares;
+DROP TABLE IF EXISTS playlist_items;
+DROP TABLE IF EXISTS playlists;
@@ -0,0 +1,39 @@
+CREATE TABLE playlists (
+    id SERIAL PRIMARY KEY,
+    playlist_id TEXT UNIQUE NOT NULL,
+    owner_id INTEGER NOT NULL REFERENCES users(id),
+    title TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    visibility TEXT NOT NULL DEFAULT 'private'
+        CHECK (visibility IN ('private', 'unlisted', 'internal', 'public')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX playlists_owner_id_idx ON playlists (owner_id);
+
+CREATE TRIGGER playlists_set_updated_at BEFORE UPDATE ON playlists
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE playlist_items (
+    id SERIAL PRIMARY KEY,
+    playlist_id INTEGER NOT NULL REFERENCES playlists(id) ON DELETE CASCADE,
+    video_id INTEGER NOT NULL REFERENCES videos(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL
+);
+
+CREATE INDEX playlist_items_playlist_id_position_idx ON playlist_items (playlist_id, position);
+
+CREATE TABLE playlist_shares (
+    id SERIAL PRIMARY KEY,
+    playlist_id INTEGER NOT NULL REFERENCES playlists(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    UNIQUE (playlist_id, user_id)
+);
+
+CREATE TABLE one_time_playlist (
+    id SERIAL PRIMARY KEY,
+    playlist_id INTEGER NOT NULL REFERENCES playlists(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    one_time_pass TEXT UNIQUE NOT NULL
+);
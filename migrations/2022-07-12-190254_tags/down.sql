@@ -0,0 +1,12 @@
+DROP TRIGGER IF EXISTS video_tags_search_vector_update ON video_tags;
+DROP FUNCTION IF EXISTS video_tags_search_vector_update();
+
+CREATE OR REPLACE FUNCTION video_search_document(video videos) RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', coalesce(video.video_name, '')), 'A') ||
+           setweight(to_tsvector('english', coalesce(video.video_desc, '')), 'B');
+$$ LANGUAGE sql STABLE;
+
+DROP TABLE IF EXISTS video_tags;
+DROP TABLE IF EXISTS tags;
+
+UPDATE videos SET search_vector = video_search_document(videos);
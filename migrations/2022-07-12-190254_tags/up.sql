@@ -0,0 +1,39 @@
+CREATE TABLE tags (
+    id SERIAL PRIMARY KEY,
+    owner_id INTEGER NOT NULL REFERENCES users(id),
+    name TEXT NOT NULL,
+    UNIQUE (owner_id, name)
+);
+
+CREATE TABLE video_tags (
+    video_id INTEGER NOT NULL REFERENCES videos(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (video_id, tag_id)
+);
+
+CREATE INDEX video_tags_tag_id_idx ON video_tags (tag_id);
+
+CREATE OR REPLACE FUNCTION video_search_document(video videos) RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', coalesce(video.video_name, '')), 'A') ||
+           setweight(to_tsvector('english', coalesce(video.video_desc, '')), 'B') ||
+           setweight(to_tsvector('english', coalesce((
+               SELECT string_agg(t.name, ' ')
+               FROM video_tags vt JOIN tags t ON t.id = vt.tag_id
+               WHERE vt.video_id = video.id
+           ), '')), 'B');
+$$ LANGUAGE sql STABLE;
+
+CREATE FUNCTION video_tags_search_vector_update() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        UPDATE videos SET search_vector = video_search_document(videos) WHERE id = OLD.video_id;
+    ELSE
+        UPDATE videos SET search_vector = video_search_document(videos) WHERE id = NEW.video_id;
+    END IF;
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER video_tags_search_vector_update
+    AFTER INSERT OR DELETE ON video_tags
+    FOR EACH ROW EXECUTE FUNCTION video_tags_search_vector_update();
@@ -0,0 +1,17 @@
+DROP TRIGGER IF EXISTS caption_tracks_search_vector_update ON caption_tracks;
+DROP FUNCTION IF EXISTS caption_tracks_search_vector_update();
+
+CREATE OR REPLACE FUNCTION video_search_document(video videos) RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', coalesce(video.video_name, '')), 'A') ||
+           setweight(to_tsvector('english', coalesce(video.video_desc, '')), 'B') ||
+           setweight(to_tsvector('english', coalesce((
+               SELECT string_agg(t.name, ' ')
+               FROM video_tags vt JOIN tags t ON t.id = vt.tag_id
+               WHERE vt.video_id = video.id
+           ), '')), 'B');
+$$ LANGUAGE sql STABLE;
+
+DROP FUNCTION IF EXISTS webvtt_text(TEXT);
+DROP TABLE IF EXISTS caption_tracks;
+
+UPDATE videos SET search_vector = video_search_document(videos);
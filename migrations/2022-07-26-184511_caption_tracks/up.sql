@@ -0,0 +1,49 @@
+CREATE TABLE caption_tracks (
+    id SERIAL PRIMARY KEY,
+    video_id INTEGER NOT NULL REFERENCES videos(id) ON DELETE CASCADE,
+    language TEXT NOT NULL,
+    label TEXT NOT NULL,
+    source TEXT NOT NULL DEFAULT 'upload',
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (video_id, language)
+);
+
+CREATE TRIGGER caption_tracks_set_updated_at BEFORE UPDATE ON caption_tracks
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Only the cue text of a WebVTT file is searchable, not the header, cue ids or timings
+CREATE FUNCTION webvtt_text(content TEXT) RETURNS TEXT AS $$
+    SELECT regexp_replace(content, '^(WEBVTT.*|NOTE.*|\d+|.*-->.*)$', '', 'gn');
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION video_search_document(video videos) RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', coalesce(video.video_name, '')), 'A') ||
+           setweight(to_tsvector('english', coalesce(video.video_desc, '')), 'B') ||
+           setweight(to_tsvector('english', coalesce((
+               SELECT string_agg(t.name, ' ')
+               FROM video_tags vt JOIN tags t ON t.id = vt.tag_id
+               WHERE vt.video_id = video.id
+           ), '')), 'B') ||
+           setweight(to_tsvector('english', coalesce((
+               SELECT string_agg(webvtt_text(c.content), ' ')
+               FROM caption_tracks c
+               WHERE c.video_id = video.id
+           ), '')), 'D');
+$$ LANGUAGE sql STABLE;
+
+CREATE FUNCTION caption_tracks_search_vector_update() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        UPDATE videos SET search_vector = video_search_document(videos) WHERE id = OLD.video_id;
+    ELSE
+        UPDATE videos SET search_vector = video_search_document(videos) WHERE id = NEW.video_id;
+    END IF;
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER caption_tracks_search_vector_update
+    AFTER INSERT OR UPDATE OF content OR DELETE ON caption_tracks
+    FOR EACH ROW EXECUTE FUNCTION caption_tracks_search_vector_update();
@@ -0,0 +1,7 @@
+CREATE TABLE chapters (
+    id SERIAL PRIMARY KEY,
+    video_id INTEGER NOT NULL REFERENCES videos(id) ON DELETE CASCADE,
+    start_seconds FLOAT NOT NULL,
+    title TEXT NOT NULL,
+    UNIQUE (video_id, start_seconds)
+);
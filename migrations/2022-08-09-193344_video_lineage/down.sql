@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS videos_source_video_id_idx;
+ALTER TABLE videos DROP COLUMN IF EXISTS source_video_id;
@@ -0,0 +1,4 @@
+-- The video a clip was cut from
+ALTER TABLE videos ADD COLUMN source_video_id INTEGER REFERENCES videos(id) ON DELETE SET NULL;
+
+CREATE INDEX videos_source_video_id_idx ON videos (source_video_id);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS video_downloads;
+ALTER TABLE videos DROP COLUMN IF EXISTS downloads_enabled;
+ALTER TABLE videos DROP COLUMN IF EXISTS original_filename;
@@ -0,0 +1,17 @@
+-- The name of the file as it was uploaded, for downloads
+ALTER TABLE videos ADD COLUMN original_filename TEXT NOT NULL DEFAULT '';
+-- video_name can be edited, the name in the URL is the one given at upload
+UPDATE videos SET original_filename = substring(video_url from '[^/]*$');
+ALTER TABLE videos ALTER COLUMN original_filename DROP DEFAULT;
+
+ALTER TABLE videos ADD COLUMN downloads_enabled BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE TABLE video_downloads (
+    id SERIAL PRIMARY KEY,
+    video_id INTEGER NOT NULL REFERENCES videos(id) ON DELETE CASCADE,
+    user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    ip_address TEXT,
+    downloaded_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX video_downloads_video_id_idx ON video_downloads (video_id, downloaded_at);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS videos_deleted_at_idx;
+ALTER TABLE videos DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,4 @@
+-- When the video was moved to the trash, NULL for videos that are not in it
+ALTER TABLE videos ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX videos_deleted_at_idx ON videos (deleted_at) WHERE deleted_at IS NOT NULL;
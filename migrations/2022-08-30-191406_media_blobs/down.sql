@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS videos_owner_sha256_idx;
+ALTER TABLE videos DROP COLUMN IF EXISTS sha256;
+DROP TABLE IF EXISTS media_blobs;
+ALTER TABLE videos ADD CONSTRAINT videos_video_path_key UNIQUE (video_path);
@@ -0,0 +1,19 @@
+-- Uploaded files, stored once per distinct content and shared by every video with that content
+CREATE TABLE media_blobs (
+    id SERIAL PRIMARY KEY,
+    sha256 TEXT NOT NULL UNIQUE,
+    path TEXT NOT NULL,
+    file_size BIGINT NOT NULL,
+    -- How many videos use the file, it is removed with the last of them
+    ref_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Every video with the same content points at the blob's file
+ALTER TABLE videos DROP CONSTRAINT videos_video_path_key;
+
+-- NULL for videos stored before hashing, until they are hashed on startup
+-- (see `media::util::backfill_video_hashes`)
+ALTER TABLE videos ADD COLUMN sha256 TEXT REFERENCES media_blobs(sha256);
+
+CREATE INDEX videos_owner_sha256_idx ON videos (owner_id, sha256) WHERE sha256 IS NOT NULL;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS storage_health;
+ALTER TABLE videos DROP COLUMN IF EXISTS thumbnail_sha256;
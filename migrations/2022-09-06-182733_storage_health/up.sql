@@ -0,0 +1,18 @@
+-- Recorded when the thumbnail is generated, so the scrub can tell if it changed since
+ALTER TABLE videos ADD COLUMN thumbnail_sha256 TEXT;
+
+-- The outcome of the last scrub of each stored file
+CREATE TABLE storage_health (
+    id SERIAL PRIMARY KEY,
+    -- 'media' or 'thumbnail'
+    kind TEXT NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    expected_sha256 TEXT NOT NULL,
+    -- NULL when the file is missing
+    actual_sha256 TEXT,
+    -- 'ok', 'missing' or 'corrupt'
+    status TEXT NOT NULL,
+    checked_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX storage_health_status_idx ON storage_health (status);
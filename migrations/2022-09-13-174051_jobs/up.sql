@@ -0,0 +1,21 @@
+-- Work done outside of requests, run by the workers of its queue
+CREATE TABLE jobs (
+    id SERIAL PRIMARY KEY,
+    -- 'media' or 'maintenance'
+    queue TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    -- The job's arguments as JSON
+    payload TEXT NOT NULL,
+    -- 'queued', 'running', 'succeeded', 'dead' or 'cancelled'
+    status TEXT NOT NULL DEFAULT 'queued',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    -- Queued jobs are not run before this, which is pushed back after each failed attempt
+    run_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX jobs_queued_idx ON jobs (queue, run_at) WHERE status = 'queued';
+CREATE INDEX jobs_status_idx ON jobs (status, id);
@@ -0,0 +1,8 @@
+-- Purging a video from the trash deletes its row, so its shares and one time links go with it
+ALTER TABLE video_shares DROP CONSTRAINT video_shares_video_id_fkey;
+ALTER TABLE video_shares ADD CONSTRAINT video_shares_video_id_fkey
+    FOREIGN KEY (video_id) REFERENCES videos(id) ON DELETE CASCADE;
+
+ALTER TABLE one_time_video DROP CONSTRAINT one_time_video_video_id_fkey;
+ALTER TABLE one_time_video ADD CONSTRAINT one_time_video_video_id_fkey
+    FOREIGN KEY (video_id) REFERENCES videos(id) ON DELETE CASCADE;
@@ -0,0 +1,2 @@
+DELETE FROM storage_health WHERE expected_sha256 IS NULL;
+ALTER TABLE storage_health ALTER COLUMN expected_sha256 SET NOT NULL;
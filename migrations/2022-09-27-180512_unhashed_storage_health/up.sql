@@ -0,0 +1,2 @@
+-- NULL for files of videos that have not been hashed yet, which can only be found missing
+ALTER TABLE storage_health ALTER COLUMN expected_sha256 DROP NOT NULL;
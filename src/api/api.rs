@@ -1,104 +1,148 @@
 use crate::auth::{sql, util};
-use crate::{auth::sql::dump_user_table, make_json_response};
+use crate::{
+    auth::sql::dump_user_table,
+    db::DbConn,
+    error::AppError,
+    models::{User, Video},
+    response::{ApiResponse, ApiResult},
+    video::util::{get_video_access, VideoAccess},
+};
 use rocket::http::CookieJar;
-use rocket::response::content::RawJson;
-use serde_json::json;
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "admin",
+    responses((status = 200, body = UserListResponse))
+)]
 #[get("/users")]
-pub async fn get_all_users(cookies: &CookieJar<'_>) -> RawJson<String> {
+pub async fn get_all_users(cookies: &CookieJar<'_>, connection: DbConn) -> ApiResult<Vec<User>> {
     let user_id = match cookies.get("user_id") {
         Some(user_id) => user_id.value().to_string(),
         None => {
             info!("Failed to get user_id from cookies");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
 
-    if !util::user_is_admin(&user_id) {
+    if !util::user_is_admin(&connection, &user_id)? {
         info!("User {} does not have permission to get all users", user_id);
-        return make_json_response!(403, "Forbidden");
+        return Err(AppError::Forbidden);
     }
 
-    match dump_user_table() {
-        Some(users) => make_json_response!(200, "OK", users),
-        None => make_json_response!(500, "Failed to dump table"),
-    }
+    let users = dump_user_table(&connection)?;
+    Ok(ApiResponse::ok(users))
 }
 
+/// Gets a single user, shares its path with `get_all_users`
+#[utoipa::path(
+    context_path = "/api",
+    path = "/users?id",
+    tag = "admin",
+    params(("id" = i32, Query, description = "The database id of the user")),
+    responses((status = 200, body = UserResponse))
+)]
 #[get("/users?<id>")]
-pub async fn get_user_by_id(id: i32, cookies: &CookieJar<'_>) -> RawJson<String> {
+pub async fn get_user_by_id(
+    id: i32,
+    cookies: &CookieJar<'_>,
+    connection: DbConn,
+) -> ApiResult<User> {
     let user_id = match cookies.get("user_id") {
         Some(user_id) => user_id.value().to_string(),
         None => {
             info!("Failed to get user_id from cookies");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
 
-    if !util::user_is_admin(&user_id) {
+    if !util::user_is_admin(&connection, &user_id)? {
         info!("User {} does not have permission to get all users", user_id);
-        return make_json_response!(403, "Forbidden");
+        return Err(AppError::Forbidden);
     }
 
-    match sql::get_user_by_id(id) {
-        Some(user) => make_json_response!(200, "OK", user),
-        None => make_json_response!(404, "User not found"),
-    }
+    let user = sql::get_user_by_id(&connection, id)?;
+    Ok(ApiResponse::ok(user))
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "admin",
+    responses((status = 200, body = VideoListResponse))
+)]
 #[get("/videos")]
-pub async fn get_all_videos(cookies: &CookieJar<'_>) -> RawJson<String> {
+pub async fn get_all_videos(cookies: &CookieJar<'_>, connection: DbConn) -> ApiResult<Vec<Video>> {
     let user_id = match cookies.get("user_id") {
         Some(user_id) => user_id.value().to_string(),
         None => {
             info!("Failed to get user_id from cookies");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
 
-    if !util::user_is_admin(&user_id) {
+    if !util::user_is_admin(&connection, &user_id)? {
         info!(
             "User {} does not have permission to get all videos",
             user_id
         );
-        return make_json_response!(403, "Forbidden");
+        return Err(AppError::Forbidden);
     }
 
-    match crate::api::sql::get_all_videos() {
-        Some(videos) => make_json_response!(200, "OK", videos),
-        None => make_json_response!(500, "Failed to load videos"),
-    }
+    let videos = crate::api::sql::get_all_videos(&connection)?;
+    Ok(ApiResponse::ok(videos))
 }
 
+/// Gets a single video the logged in user may view, shares its path with `get_all_videos`
+#[utoipa::path(
+    context_path = "/api",
+    path = "/videos?id",
+    tag = "video",
+    params(("id" = String, Query, description = "The public id of the video")),
+    responses((status = 200, body = VideoResponse))
+)]
 #[get("/videos?<id>")]
-pub async fn get_video_with_id(id: String, cookies: &CookieJar<'_>) -> RawJson<String> {
+pub async fn get_video_with_id(
+    id: String,
+    cookies: &CookieJar<'_>,
+    connection: DbConn,
+) -> ApiResult<Video> {
     let user_id = match cookies.get("user_id") {
         Some(user_id) => user_id.value().to_string(),
         None => {
             info!("Failed to get user_id from cookies");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
 
-    let user = match sql::get_user_by_user_id(&user_id) {
-        Some(user) => user,
-        None => {
+    let user = match sql::get_user_by_user_id(&connection, &user_id) {
+        Ok(user) => user,
+        Err(AppError::NotFound) => {
             info!("Failed to get user with user_id {}", user_id);
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
+        Err(e) => return Err(e),
     };
 
-    match crate::api::sql::get_video_with_id(&id) {
-        Some(v) => {
-            if v.owner_id != user.id && !util::user_is_admin(user) {
-                info!(
-                    "User {} does not have permission to view video {}",
-                    user_id, id
-                );
-                return make_json_response!(403, "Forbidden");
-            }
-            make_json_response!(200, "OK", v)
+    let video = crate::api::sql::get_video_with_id(&connection, &id)?;
+    match get_video_access(&connection, &video, Some(&user), cookies)? {
+        VideoAccess::Allowed => Ok(ApiResponse::ok(video)),
+        VideoAccess::PasswordRequired => Err(AppError::PasswordRequired),
+        VideoAccess::Denied => {
+            info!(
+                "User {} does not have permission to view video {}",
+                user_id, id
+            );
+            Err(AppError::Forbidden)
         }
-        None => make_json_response!(404, "Video not found"),
     }
 }
+
+#[utoipa::path(
+    context_path = "/api",
+    tag = "video",
+    responses((status = 200, body = VideoListResponse))
+)]
+#[get("/videos/public")]
+pub async fn get_public_videos(connection: DbConn) -> ApiResult<Vec<Video>> {
+    let videos = crate::api::sql::get_public_videos(&connection)?;
+    Ok(ApiResponse::ok(videos))
+}
@@ -1,35 +1,30 @@
-use crate::{create_connection, models::Video};
+use crate::{
+    error::AppResult,
+    models::Video,
+    video::model::{VideoStatus, Visibility},
+};
 use diesel::prelude::*;
 
-pub fn get_video_with_id(id: &String) -> Option<Video> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match crate::schema::videos::table
+pub fn get_video_with_id(connection: &PgConnection, id: &String) -> AppResult<Video> {
+    Ok(crate::schema::videos::table
         .filter(crate::schema::videos::dsl::video_id.eq(id.clone()))
-        .get_result::<Video>(&connection)
-    {
-        Ok(video) => Some(video),
-        Err(e) => {
-            if e == diesel::NotFound {
-                info!("Failed to get video with id {} with error {}", id, e);
-            } else {
-                warn!("Failed to get video with id : {} (error {})", id, e);
-            }
-            None
-        }
-    }
+        .filter(crate::schema::videos::dsl::deleted_at.is_null())
+        .get_result::<Video>(connection)?)
 }
 
-pub fn get_all_videos() -> Option<Vec<Video>> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match crate::schema::videos::table.load::<Video>(&connection) {
-        Ok(videos) => Some(videos),
-        Err(e) => {
-            if e == diesel::NotFound {
-                info!("Failed to get videos with error {}", e);
-            } else {
-                warn!("Failed to get videos (error {})", e);
-            }
-            None
-        }
-    }
+pub fn get_all_videos(connection: &PgConnection) -> AppResult<Vec<Video>> {
+    Ok(crate::schema::videos::table
+        .filter(crate::schema::videos::dsl::deleted_at.is_null())
+        .load::<Video>(connection)?)
+}
+
+/// Gets the videos anyone can watch, leaving out password protected ones and
+/// those still processing or failed
+pub fn get_public_videos(connection: &PgConnection) -> AppResult<Vec<Video>> {
+    Ok(crate::schema::videos::table
+        .filter(crate::schema::videos::dsl::visibility.eq(Visibility::Public.as_str()))
+        .filter(crate::schema::videos::dsl::password_hash.is_null())
+        .filter(crate::schema::videos::dsl::status.eq(VideoStatus::Ready.as_str()))
+        .filter(crate::schema::videos::dsl::deleted_at.is_null())
+        .load::<Video>(connection)?)
 }
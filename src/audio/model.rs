@@ -0,0 +1,49 @@
+use std::str::FromStr;
+
+/// Formats the audio track of a video can be downloaded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioFormat {
+    M4a,
+    Mp3,
+    Opus,
+}
+
+impl AudioFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+        }
+    }
+
+    /// The codec ffmpeg reports for audio that can be copied into this format without transcoding
+    pub fn copyable_codec(&self) -> &'static str {
+        match self {
+            AudioFormat::M4a => "aac",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+        }
+    }
+
+    /// ffmpeg arguments to transcode into this format
+    pub fn encoder_args(&self) -> [&'static str; 4] {
+        match self {
+            AudioFormat::M4a => ["-c:a", "aac", "-b:a", "160k"],
+            AudioFormat::Mp3 => ["-c:a", "libmp3lame", "-q:a", "2"],
+            AudioFormat::Opus => ["-c:a", "libopus", "-b:a", "96k"],
+        }
+    }
+}
+
+impl FromStr for AudioFormat {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "m4a" => Ok(AudioFormat::M4a),
+            "mp3" => Ok(AudioFormat::Mp3),
+            "opus" => Ok(AudioFormat::Opus),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{
+    audio::{
+        model::AudioFormat,
+        util::{get_or_extract_audio, AudioExtractions},
+    },
+    auth::util::get_user_from_cookies,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    video::{
+        sql::get_video_by_video_id,
+        util::{get_video_access, VideoAccess},
+    },
+};
+use rocket::http::CookieJar;
+use rocket::State;
+use rocket_seek_stream::SeekStream;
+
+/// Streams the audio track of a video as `m4a`, `mp3` or `opus` (defaults to `m4a`)
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "audio",
+    responses((status = 200, description = "The audio track, supports range requests"))
+)]
+#[get("/<id>/audio?<format>")]
+pub async fn get_audio<'a>(
+    id: String,
+    format: Option<String>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+    extractions: &State<AudioExtractions>,
+) -> AppResult<SeekStream<'a>> {
+    let format = match format {
+        Some(format) => format
+            .parse::<AudioFormat>()
+            .map_err(|_| AppError::BadRequest)?,
+        None => AudioFormat::M4a,
+    };
+
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if get_video_access(&connection, &video, user.as_ref(), cookies)? != VideoAccess::Allowed {
+        return Err(AppError::NotFound);
+    }
+    // Extracting can take a while, don't keep a connection from other requests
+    drop(connection);
+
+    let path = get_or_extract_audio(extractions, &video, format)
+        .await
+        .ok_or_else(|| {
+            AppError::Probe(format!(
+                "Failed to extract audio of video {}",
+                video.video_id
+            ))
+        })?;
+    SeekStream::from_path(path).map_err(AppError::from_file_error)
+}
@@ -0,0 +1,173 @@
+use crate::{
+    audio::model::AudioFormat, models::Video, util::make_random_string, video::util::probe_media,
+};
+use rocket::tokio::{fs, sync::Mutex as AsyncMutex};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tokio::process::Command;
+
+pub const AUDIO_FORMATS: [AudioFormat; 3] = [AudioFormat::M4a, AudioFormat::Mp3, AudioFormat::Opus];
+
+pub fn cached_audio_path(video: &Video, format: AudioFormat) -> String {
+    format!(
+        "audio/{}/{}.{}",
+        video.owner_id,
+        video.video_id,
+        format.extension()
+    )
+}
+
+type ExtractionLocks = HashMap<(String, AudioFormat), Arc<AsyncMutex<()>>>;
+
+/// One lock per video and format being extracted, managed as Rocket state, so
+/// concurrent requests wait for a single ffmpeg run instead of starting their own
+#[derive(Default)]
+pub struct AudioExtractions(Mutex<ExtractionLocks>);
+
+impl AudioExtractions {
+    fn locks(&self) -> std::sync::MutexGuard<'_, ExtractionLocks> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_for(&self, key: &(String, AudioFormat)) -> Arc<AsyncMutex<()>> {
+        self.locks().entry(key.clone()).or_default().clone()
+    }
+
+    /// Forgets the lock of a finished extraction once nobody else is waiting on it
+    fn release(&self, key: &(String, AudioFormat), lock: Arc<AsyncMutex<()>>) {
+        let mut locks = self.locks();
+        // Held by the map and `lock`, new holders only clone it while the map is locked
+        if Arc::strong_count(&lock) == 2 {
+            locks.remove(key);
+        }
+    }
+}
+
+/// Gets the codec name of a video's first audio stream, if ffprobe reports one.
+/// Returns None when the video has no audio or could not be probed.
+async fn probe_audio_codec(video: &Video) -> Option<Option<String>> {
+    let probe = match probe_media(&video.video_path).await {
+        Ok(probe) => probe,
+        Err(e) => {
+            warn!("Failed to probe video {} for audio ({})", video.video_id, e);
+            return None;
+        }
+    };
+    Some(
+        probe
+            .streams
+            .into_iter()
+            .find(|stream| stream.codec_type.as_deref() == Some("audio"))?
+            .codec_name,
+    )
+}
+
+/// Gets the path of a video's audio track in `format`, extracting it on the first request.
+/// The audio is copied as is when it is already in the right codec and transcoded otherwise.
+pub async fn get_or_extract_audio(
+    extractions: &AudioExtractions,
+    video: &Video,
+    format: AudioFormat,
+) -> Option<String> {
+    let path = cached_audio_path(video, format);
+    if Path::new(&path).exists() {
+        return Some(path);
+    }
+
+    let key = (video.video_id.clone(), format);
+    let lock = extractions.lock_for(&key);
+    let guard = lock.lock().await;
+    // Another request may have extracted it while this one waited
+    let extracted = if Path::new(&path).exists() {
+        Some(path)
+    } else {
+        extract_audio(video, format, path).await
+    };
+    drop(guard);
+    extractions.release(&key, lock);
+    extracted
+}
+
+async fn extract_audio(video: &Video, format: AudioFormat, path: String) -> Option<String> {
+    let codec = match probe_audio_codec(video).await {
+        Some(codec) => codec,
+        None => {
+            info!("Video {} has no audio track", video.video_id);
+            return None;
+        }
+    };
+
+    let folder = format!("audio/{}", video.owner_id);
+    if let Err(e) = fs::create_dir_all(&folder).await {
+        warn!("Failed to create folder {} with error: {}", folder, e);
+        return None;
+    }
+
+    let temp_path = format!(
+        "{}/{}.{}.{}",
+        folder,
+        video.video_id,
+        make_random_string(8),
+        format.extension()
+    );
+    let mut command = Command::new("ffmpeg");
+    command.args([
+        "-v",
+        "error",
+        "-y",
+        "-i",
+        &video.video_path,
+        "-vn",
+        "-map",
+        "0:a:0",
+    ]);
+    if codec.as_deref() == Some(format.copyable_codec()) {
+        command.args(["-c:a", "copy"]);
+    } else {
+        command.args(format.encoder_args());
+    }
+    // A dropped request stops ffmpeg with it
+    match command.arg(&temp_path).kill_on_drop(true).output().await {
+        Ok(output) if output.status.success() => (),
+        Ok(output) => {
+            warn!(
+                "ffmpeg failed to extract audio of video {}: {}",
+                video.video_id,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let _ = fs::remove_file(&temp_path).await;
+            return None;
+        }
+        Err(e) => {
+            warn!("Failed to run ffmpeg with error: {}", e);
+            return None;
+        }
+    }
+
+    match fs::rename(&temp_path, &path).await {
+        Ok(_) => Some(path),
+        Err(e) => {
+            warn!(
+                "Failed to move audio {} to {} with error: {}",
+                temp_path, path, e
+            );
+            let _ = fs::remove_file(&temp_path).await;
+            None
+        }
+    }
+}
+
+/// Removes every cached audio track of a video
+pub async fn remove_cached_audio(video: &Video) {
+    for format in AUDIO_FORMATS {
+        let path = cached_audio_path(video, format);
+        if Path::new(&path).exists() {
+            if let Err(e) = fs::remove_file(&path).await {
+                warn!("Failed to remove cached audio {} with error: {}", path, e);
+            }
+        }
+    }
+}
@@ -1,7 +1,14 @@
-use super::sql::{get_user_by_email, insert_user};
+use super::sql::{get_user_by_email, insert_user, record_user_login};
 use super::util::oauth_token_is_valid;
 use crate::auth::sql::get_user_by_user_id;
-use crate::{make_json_response, unwrap_or_return_option};
+use crate::{
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::User,
+    response::{ApiResponse, ApiResult},
+    unwrap_or_return_option,
+};
+use diesel::PgConnection;
 use oauth2::basic::{BasicClient, BasicErrorResponseType, BasicTokenType};
 use oauth2::reqwest::async_http_client;
 use oauth2::{
@@ -11,11 +18,10 @@ use oauth2::{
     StandardTokenResponse, TokenResponse, TokenUrl,
 };
 use rocket::http::{Cookie, CookieJar, SameSite, Status};
-use rocket::response::content::RawJson;
 use rocket::response::Redirect;
+use rocket::State;
 use rocket_oauth2::OAuth2;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use std::env;
 
 pub type OAuth2Client = Client<
@@ -122,6 +128,11 @@ async fn revoke_oauth<T: ToString>(
     }
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to Google's consent screen"))
+)]
 #[get("/login/google")]
 pub async fn google_login() -> Redirect {
     Redirect::to(
@@ -143,9 +154,19 @@ pub async fn google_login() -> Redirect {
     )
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to the frontend after logging in"))
+)]
 #[get("/auth/google?<state>&<code>")]
 #[allow(unused_variables)] // Rocket doesn't like unused variables naming scheme '_state'
-pub async fn google_callback(state: String, code: String, cookies: &CookieJar<'_>) -> Redirect {
+pub async fn google_callback(
+    state: String,
+    code: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> Redirect {
     let failure_redirect = Redirect::to("/login");
     let client = match generate_oauth_client(
         env::var("GOOGLE_CLIENT_ID").expect("Missing the GOOGLE_CLIENT_ID environment variable."),
@@ -207,18 +228,21 @@ pub async fn google_callback(state: String, code: String, cookies: &CookieJar<'_
 
                 info!("Successfully got google response: {:?}", google_response);
                 let email = google_response.email;
-                let user = if let Some(user) = get_user_by_email(email.to_owned()) {
-                    user
-                } else {
-                    match insert_user(email.to_owned()) {
-                        Some(user) => user,
-                        None => {
-                            warn!("Failed to insert new user {}", email);
-                            return failure_redirect;
-                        }
+                let connection = match get_connection(pool).await {
+                    Some(connection) => connection,
+                    None => return failure_redirect,
+                };
+                let user = match get_or_insert_user(&connection, &email) {
+                    Ok(user) => user,
+                    Err(e) => {
+                        warn!("Failed to get or insert user {} ({})", email, e);
+                        return failure_redirect;
                     }
                 };
                 info!("Got user {:?}", user);
+                if let Err(e) = record_user_login(&connection, user.id) {
+                    warn!("Failed to record login for user {} ({})", user.id, e);
+                }
                 cookies.add(
                     Cookie::build("user_id", user.user_id.clone())
                         .same_site(SameSite::Lax)
@@ -260,6 +284,11 @@ pub async fn google_logout(token: String) -> bool {
     .await
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to Discord's consent screen"))
+)]
 #[get("/login/discord")]
 pub async fn discord_login() -> Redirect {
     Redirect::to(
@@ -281,8 +310,17 @@ pub async fn discord_login() -> Redirect {
     )
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to the frontend after logging in"))
+)]
 #[get("/auth/discord?<code>")]
-pub async fn discord_callback(code: String, cookies: &CookieJar<'_>) -> Redirect {
+pub async fn discord_callback(
+    code: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> Redirect {
     info!("Got discord callback with code: {}", code);
     let failure_redirect = Redirect::to("/login");
     let client = match generate_oauth_client(
@@ -341,18 +379,21 @@ pub async fn discord_callback(code: String, cookies: &CookieJar<'_>) -> Redirect
             .replace("\"", "")
             .replace(" ", "");
             info!("Got email: {}", email);
-            let user = if let Some(user) = get_user_by_email(email.to_owned()) {
-                user
-            } else {
-                match insert_user(email.to_owned()) {
-                    Some(user) => user,
-                    None => {
-                        warn!("Failed to insert new user {}", email);
-                        return failure_redirect;
-                    }
+            let connection = match get_connection(pool).await {
+                Some(connection) => connection,
+                None => return failure_redirect,
+            };
+            let user = match get_or_insert_user(&connection, &email) {
+                Ok(user) => user,
+                Err(e) => {
+                    warn!("Failed to get or insert user {} ({})", email, e);
+                    return failure_redirect;
                 }
             };
             info!("Got user {:?}", user);
+            if let Err(e) = record_user_login(&connection, user.id) {
+                warn!("Failed to record login for user {} ({})", user.id, e);
+            }
             cookies.add(
                 Cookie::build("user_id", user.user_id.clone())
                     .same_site(SameSite::Lax)
@@ -388,6 +429,11 @@ async fn discord_logout(token: String) -> bool {
 }
 
 // TODO hogbisz oauth update to oauth2 crate
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to Hogbisz's consent screen"))
+)]
 #[get("/login/hogbisz")]
 pub async fn hogbisz_login(oauth2: OAuth2<Hogbisz>, cookies: &CookieJar<'_>) -> Redirect {
     oauth2
@@ -395,6 +441,13 @@ pub async fn hogbisz_login(oauth2: OAuth2<Hogbisz>, cookies: &CookieJar<'_>) ->
         .unwrap()
 }
 
+/// Hogbisz has no userinfo endpoint, so no user is known after logging in and
+/// neither a `user_id` cookie is set nor the login recorded
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to the frontend after logging in"))
+)]
 #[get("/auth/hogbisz")]
 pub async fn hogbisz_callback(
     token: rocket_oauth2::TokenResponse<Hogbisz>,
@@ -417,8 +470,13 @@ async fn hogbisz_logout(_token: String) -> bool {
     false
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to the frontend with whether the token was revoked"))
+)]
 #[get("/logout")]
-pub async fn logout(cookies: &CookieJar<'_>) -> Redirect {
+pub async fn logout(cookies: &CookieJar<'_>, pool: &State<DbPool>) -> Redirect {
     let mut redirect = Redirect::to("/?logout=false");
 
     if let (Some(oauth_type), Some(token), Some(user_id)) = (
@@ -430,7 +488,10 @@ pub async fn logout(cookies: &CookieJar<'_>) -> Redirect {
         let token = token.value().to_string();
         let user_id = user_id.value().to_string();
 
-        if oauth_token_is_valid(oauth_type.clone(), token.clone(), user_id).await {
+        if oauth_token_is_valid(pool, oauth_type.clone(), token.clone(), user_id)
+            .await
+            .unwrap_or(false)
+        {
             // Implement error response query
             if match oauth_type.as_str() {
                 "discord" => discord_logout(token).await,
@@ -450,27 +511,41 @@ pub async fn logout(cookies: &CookieJar<'_>) -> Redirect {
     redirect
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 200, description = "The user was created"))
+)]
 #[post("/auth/create_user?<email>")]
-pub async fn create_user(email: String) -> Status {
-    match insert_user(email) {
-        Some(user) => {
-            info!("Created user: {}", user.email);
-            Status::Ok
-        }
-        None => {
-            info!("Failed to create user");
-            Status::InternalServerError
-        }
+pub async fn create_user(email: String, pool: &State<DbPool>) -> AppResult<Status> {
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    let user = insert_user(&connection, email)?;
+    info!("Created user: {}", user.email);
+    Ok(Status::Ok)
+}
+
+/// Gets the user with `email`, creating them on their first login
+fn get_or_insert_user(connection: &PgConnection, email: &String) -> AppResult<User> {
+    match get_user_by_email(connection, email.to_owned()) {
+        Err(AppError::NotFound) => insert_user(connection, email.to_owned()),
+        result => result,
     }
 }
 
+#[utoipa::path(
+    context_path = "/api",
+    tag = "auth",
+    responses((status = 200, body = UserResponse))
+)]
 #[get("/auth/me")]
-pub async fn me(cookies: &CookieJar<'_>) -> RawJson<String> {
+pub async fn me(cookies: &CookieJar<'_>, pool: &State<DbPool>) -> ApiResult<User> {
     let oauth_type = match cookies.get("oauth") {
         Some(cookie) => cookie.value().to_string(),
         None => {
             info!("Failed to get oauth type");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
 
@@ -478,24 +553,25 @@ pub async fn me(cookies: &CookieJar<'_>) -> RawJson<String> {
         Some(cookie) => cookie.value().to_string(),
         None => {
             info!("Failed to get oauth token");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
     let user_id = match cookies.get("user_id") {
         Some(cookie) => cookie.value().to_string(),
         None => {
             info!("No user_id cookie found");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
 
-    if !oauth_token_is_valid(oauth_type.clone(), token.clone(), user_id.clone()).await {
+    if !oauth_token_is_valid(pool, oauth_type.clone(), token.clone(), user_id.clone()).await? {
         info!("Failed to validate oauth token!");
-        return make_json_response!(401, "Unauthorized");
+        return Err(AppError::Unauthorized);
     }
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
 
-    match get_user_by_user_id(&user_id) {
-        Some(user) => make_json_response!(200, "OK", user),
-        None => make_json_response!(500, "Internal Server Error"),
-    }
+    let user = get_user_by_user_id(&connection, &user_id)?;
+    Ok(ApiResponse::ok(user))
 }
@@ -1,128 +1,67 @@
 use crate::{
-    create_connection,
+    error::AppResult,
     models::{User, UserNoId},
     util::make_random_string,
 };
-use diesel::prelude::*;
+use diesel::{dsl::now, prelude::*};
 
-pub fn generate_new_user_id() -> String {
+pub fn generate_new_user_id(connection: &PgConnection) -> AppResult<String> {
     let mut user_id = make_random_string(32);
-    while let Some(_) = get_user_by_user_id_no_error(&user_id) {
+    while get_user_by_user_id_no_error(connection, &user_id)?.is_some() {
         user_id = make_random_string(32);
     }
-    user_id
+    Ok(user_id)
 }
 
-pub fn insert_user(email: String) -> Option<User> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match diesel::insert_into(crate::schema::users::table)
-        .values(&UserNoId {
-            email: email.clone(),
-            displayname: email.clone(),
-            user_id: generate_new_user_id(),
-        })
-        .get_result::<User>(&connection)
-    {
-        Ok(user) => Some(user),
-        Err(e) => {
-            info!("Failed to insert user (error {})", e);
-            None
-        }
-    }
+pub fn insert_user(connection: &PgConnection, email: String) -> AppResult<User> {
+    Ok(diesel::insert_into(crate::schema::users::table)
+        .values((
+            &UserNoId {
+                email: email.clone(),
+                displayname: email.clone(),
+                user_id: generate_new_user_id(connection)?,
+            },
+            crate::schema::users::dsl::created_at.eq(now),
+            crate::schema::users::dsl::updated_at.eq(now),
+        ))
+        .get_result::<User>(connection)?)
+}
+
+pub fn record_user_login(connection: &PgConnection, id: i32) -> AppResult<()> {
+    diesel::update(crate::schema::users::table.filter(crate::schema::users::dsl::id.eq(id)))
+        .set(crate::schema::users::dsl::last_login_at.eq(now))
+        .execute(connection)?;
+    Ok(())
 }
 
-fn get_user_by_user_id_no_error(user_id: &String) -> Option<User> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match crate::schema::users::table
+fn get_user_by_user_id_no_error(
+    connection: &PgConnection,
+    user_id: &String,
+) -> AppResult<Option<User>> {
+    Ok(crate::schema::users::table
         .filter(crate::schema::users::dsl::user_id.eq(user_id.to_owned()))
-        .first::<User>(&connection)
-    {
-        Ok(user) => Some(user),
-        Err(_) => None,
-    }
+        .first::<User>(connection)
+        .optional()?)
 }
 
-pub fn get_user_by_email(email: String) -> Option<User> {
-    let connection = match crate::create_connection() {
-        Some(connection) => connection,
-        None => {
-            warn!("Failed to get connection to database");
-            return None;
-        }
-    };
-    match crate::schema::users::table
+pub fn get_user_by_email(connection: &PgConnection, email: String) -> AppResult<User> {
+    Ok(crate::schema::users::table
         .filter(crate::schema::users::dsl::email.eq(email.to_owned()))
-        .get_result::<User>(&connection)
-    {
-        Ok(user) => Some(user),
-        Err(e) => {
-            if e != diesel::NotFound {
-                warn!("Failed to get user {} with error {}", email, e);
-            }
-            None
-        }
-    }
+        .get_result::<User>(connection)?)
 }
 
-pub fn get_user_by_user_id(user_id: &String) -> Option<User> {
-    let connection = match crate::create_connection() {
-        Some(connection) => connection,
-        None => {
-            warn!("Failed to get connection to database");
-            return None;
-        }
-    };
-    match crate::schema::users::table
+pub fn get_user_by_user_id(connection: &PgConnection, user_id: &String) -> AppResult<User> {
+    Ok(crate::schema::users::table
         .filter(crate::schema::users::dsl::user_id.eq(user_id.to_owned()))
-        .get_result::<User>(&connection)
-    {
-        Ok(user) => Some(user),
-        Err(e) => {
-            if e != diesel::NotFound {
-                warn!("Failed to get user {} with error {}", user_id, e);
-            }
-            None
-        }
-    }
+        .get_result::<User>(connection)?)
 }
 
-pub fn get_user_by_id(id: i32) -> Option<User> {
-    let connection = match crate::create_connection() {
-        Some(connection) => connection,
-        None => {
-            warn!("Failed to get connection to database");
-            return None;
-        }
-    };
-    match crate::schema::users::table
+pub fn get_user_by_id(connection: &PgConnection, id: i32) -> AppResult<User> {
+    Ok(crate::schema::users::table
         .filter(crate::schema::users::dsl::id.eq(id))
-        .get_result::<User>(&connection)
-    {
-        Ok(user) => Some(user),
-        Err(e) => {
-            if e != diesel::NotFound {
-                warn!("Failed to get user {} with error {}", id, e);
-            }
-            None
-        }
-    }
+        .get_result::<User>(connection)?)
 }
 
-pub fn dump_user_table() -> Option<Vec<User>> {
-    let connection = match crate::create_connection() {
-        Some(connection) => connection,
-        None => {
-            warn!("Failed to get connection to database");
-            return None;
-        }
-    };
-    match crate::schema::users::table.load::<User>(&connection) {
-        Ok(users) => Some(users),
-        Err(e) => {
-            if e != diesel::NotFound {
-                warn!("Failed to get users with error {}", e);
-            }
-            None
-        }
-    }
+pub fn dump_user_table(connection: &PgConnection) -> AppResult<Vec<User>> {
+    Ok(crate::schema::users::table.load::<User>(connection)?)
 }
@@ -1,38 +1,121 @@
-use crate::{auth::sql::get_user_by_user_id, models::User};
+use crate::{
+    auth::sql::get_user_by_user_id,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::User,
+};
+use diesel::PgConnection;
+use rocket::http::CookieJar;
 
-pub fn user_is_admin<T: TryInto<User>>(user_id: T) -> bool {
-    let user: User = match user_id.try_into() {
+pub fn user_is_admin(connection: &PgConnection, user_id: &String) -> AppResult<bool> {
+    let user = match get_user_by_user_id(connection, user_id) {
         Ok(user) => user,
-        Err(_) => {
-            info!("Failed to convert user_id to User");
-            return false;
+        Err(AppError::NotFound) => {
+            info!("Failed to get user with user_id {}", user_id);
+            return Ok(false);
         }
+        Err(e) => return Err(e),
     };
 
-    if !user.permissions.contains(&1) {
+    if !user_has_admin_permission(&user) {
         info!(
             "User {} does not have permission to get all users",
             user.user_id
         );
-        return false;
+        return Ok(false);
     }
-    true
+    Ok(true)
 }
 
-pub async fn oauth_token_is_valid<T: Into<String>>(oauth: T, token: T, user_id: T) -> bool {
-    let oauth_type = oauth.into();
-    match oauth_type.as_str() {
-        "google" => validate_google_token(token.into(), user_id.into()).await,
-        "discord" => validate_discord_token(token.into(), user_id.into()).await,
-        "hogbisz" => validate_hogbisz_token(token.into(), user_id.into()).await,
+pub fn user_has_admin_permission(user: &User) -> bool {
+    user.permissions.contains(&1)
+}
+
+/// Gets the logged in user from the request cookies, validating their oauth token.
+/// Returns None for anonymous users or invalid tokens.
+///
+/// Takes the pool rather than a connection, since validating the token can mean waiting
+/// on the oauth provider and a held connection would be unusable for that long.
+pub async fn get_user_from_cookies(
+    pool: &DbPool,
+    cookies: &CookieJar<'_>,
+) -> AppResult<Option<User>> {
+    let user_id = match cookies.get("user_id") {
+        Some(cookie) => cookie.value().to_string(),
+        None => return Ok(None),
+    };
+    let oauth_type = match cookies.get("oauth") {
+        Some(cookie) => cookie.value().to_string(),
+        None => {
+            info!("No oauth type cookie found");
+            return Ok(None);
+        }
+    };
+    let token = match cookies.get_private("token") {
+        Some(cookie) => cookie.value().to_string(),
+        None => {
+            info!("No token cookie found");
+            return Ok(None);
+        }
+    };
+
+    let user = get_user_for_token(pool, oauth_type, token, user_id.clone()).await?;
+    if user.is_none() {
+        info!("User {} had an invalid token", user_id);
+    }
+    Ok(user)
+}
+
+pub async fn oauth_token_is_valid<T: Into<String>>(
+    pool: &DbPool,
+    oauth: T,
+    token: T,
+    user_id: T,
+) -> AppResult<bool> {
+    Ok(
+        get_user_for_token(pool, oauth.into(), token.into(), user_id.into())
+            .await?
+            .is_some(),
+    )
+}
+
+/// Gets the user with `user_id` if the oauth provider vouches for `token` belonging to them.
+/// The provider is asked before a connection is taken from the pool.
+async fn get_user_for_token(
+    pool: &DbPool,
+    oauth_type: String,
+    token: String,
+    user_id: String,
+) -> AppResult<Option<User>> {
+    let email = match oauth_type.as_str() {
+        "google" => get_google_token_email(token).await,
+        "discord" => get_discord_token_email(token).await,
+        "hogbisz" => get_hogbisz_token_email(token).await,
         _ => {
             info!("Provided oauth type {} is not supported", oauth_type);
-            false
+            return Ok(None);
         }
+    };
+    let email = match email {
+        Some(email) => email,
+        None => return Ok(None),
+    };
+
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    match get_user_by_user_id(&connection, &user_id) {
+        Ok(user) if user.email == email => Ok(Some(user)),
+        Ok(_) => Ok(None),
+        Err(AppError::NotFound) => {
+            info!("No user found with user_id {}", user_id);
+            Ok(None)
+        }
+        Err(e) => Err(e),
     }
 }
 
-async fn validate_google_token(token: String, user_id: String) -> bool {
+async fn get_google_token_email(token: String) -> Option<String> {
     let client = reqwest::Client::new();
 
     match client
@@ -54,36 +137,28 @@ async fn validate_google_token(token: String, user_id: String) -> bool {
                         Some(email) => email.to_string(),
                         None => {
                             info!("Failed to get email from Google's response body");
-                            return false;
+                            return None;
                         }
                     },
                     None => {
                         info!("Failed to get email from Google's response body");
-                        return false;
+                        return None;
                     }
                 };
                 info!("Google's email: {}", google_email);
-                let user = match get_user_by_user_id(&user_id) {
-                    Some(user) => user,
-                    None => {
-                        info!("No user found with user_id {}", user_id);
-                        return false;
-                    }
-                };
-
-                google_email == user.email
+                Some(google_email)
             } else {
-                false
+                None
             }
         }
         Err(e) => {
             info!("Failed to validate google token with error {}", e);
-            false
+            None
         }
     }
 }
 
-async fn validate_discord_token(token: String, user_id: String) -> bool {
+async fn get_discord_token_email(token: String) -> Option<String> {
     let client = reqwest::Client::new();
 
     match client
@@ -103,35 +178,29 @@ async fn validate_discord_token(token: String, user_id: String) -> bool {
                         Some(email) => email.to_string(),
                         None => {
                             info!("Failed to get email from Discord's response body");
-                            return false;
+                            return None;
                         }
                     },
                     None => {
                         info!("Failed to get email from Discord's response body");
-                        return false;
+                        return None;
                     }
                 };
                 info!("Discord's email: {}", discord_email);
-                let user = match get_user_by_user_id(&user_id) {
-                    Some(user) => user,
-                    None => {
-                        info!("No user found with user_id {}", user_id);
-                        return false;
-                    }
-                };
-                discord_email == user.email
+                Some(discord_email)
             } else {
-                false
+                None
             }
         }
         Err(e) => {
             info!("Failed to validate discord token with error {}", e);
-            false
+            None
         }
     }
 }
 
+/// Gets the email of the Hogbisz account a token belongs to
 // TODO: Implement
-async fn validate_hogbisz_token(_token: String, _user_id: String) -> bool {
-    false
+pub async fn get_hogbisz_token_email(_token: String) -> Option<String> {
+    None
 }
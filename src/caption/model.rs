@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// Formats caption files can be uploaded in. Everything is stored as WebVTT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionFormat {
+    Srt,
+    Vtt,
+}
+
+impl FromStr for CaptionFormat {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "srt" => Ok(CaptionFormat::Srt),
+            "vtt" => Ok(CaptionFormat::Vtt),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where a caption track came from, stored as text in `caption_tracks.source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionSource {
+    /// Uploaded by the video's owner
+    Upload,
+    /// Extracted from a subtitle stream in the video file
+    Embedded,
+    /// Generated by transcribing the video's audio
+    Transcript,
+}
+
+impl CaptionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptionSource::Upload => "upload",
+            CaptionSource::Embedded => "embedded",
+            CaptionSource::Transcript => "transcript",
+        }
+    }
+}
+
+/// A caption track as listed next to a video, without its content
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CaptionTrackInfo {
+    pub language: String,
+    pub label: String,
+    pub source: String,
+    /// Where the WebVTT file can be fetched from
+    pub url: String,
+}
+
+impl CaptionTrackInfo {
+    pub fn new(video_id: &str, language: String, label: String, source: String) -> Self {
+        CaptionTrackInfo {
+            url: format!("/api/video/{}/captions/{}.vtt", video_id, language),
+            language,
+            label,
+            source,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    auth::util::get_user_from_cookies,
+    caption::{
+        model::{CaptionFormat, CaptionSource},
+        sql::{delete_caption_track, get_caption_track, upsert_caption_track},
+        util::{
+            caption_file_to_vtt, detect_caption_format, normalize_language, sanitize_caption_label,
+        },
+    },
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::{CaptionTrack, CaptionTrackNoId},
+    response::{ApiMessage, ApiMessageResult, ApiResponse, ApiResult},
+    video::{
+        sql::get_video_by_video_id,
+        util::{get_video_access, VideoAccess},
+    },
+};
+use rocket::{
+    data::{Data, ToByteUnit},
+    http::{ContentType, CookieJar},
+    State,
+};
+
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "caption",
+    responses((status = 200, description = "The caption track as WebVTT", body = String, content_type = "text/vtt"))
+)]
+#[get("/<id>/captions/<filename>")]
+pub async fn get_captions(
+    id: String,
+    filename: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> AppResult<(ContentType, String)> {
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let language = match filename.strip_suffix(".vtt").and_then(normalize_language) {
+        Some(language) => language,
+        None => {
+            info!("Invalid caption filename {}", filename);
+            return Err(AppError::NotFound);
+        }
+    };
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if get_video_access(&connection, &video, user.as_ref(), cookies)? != VideoAccess::Allowed {
+        return Err(AppError::NotFound);
+    }
+
+    let track = get_caption_track(&connection, video.id, &language)?;
+
+    Ok((ContentType::new("text", "vtt"), track.content))
+}
+
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "caption",
+    request_body(content = String, description = "An SRT or WebVTT file", content_type = "text/plain"),
+    responses((status = 200, body = CaptionTrackResponse))
+)]
+#[post("/<id>/captions/<language>?<format>&<label>", data = "<captions>")]
+pub async fn upload_captions(
+    id: String,
+    language: String,
+    format: Option<String>,
+    label: Option<String>,
+    captions: Data<'_>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<CaptionTrack> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if video.owner_id != user.id {
+        info!("User {} is not the owner of video {}", user.id, video.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let language = match normalize_language(&language) {
+        Some(language) => language,
+        None => {
+            info!("Invalid caption language {}", language);
+            return Err(AppError::BadRequest);
+        }
+    };
+
+    let content = match captions.open(2.mebibytes()).into_string().await {
+        Ok(content) if content.is_complete() => content.into_inner(),
+        Ok(_) => {
+            info!("Caption file for video {} is too large", video.id);
+            return Err(AppError::PayloadTooLarge);
+        }
+        Err(e) => {
+            info!("Failed to read caption file with error: {}", e);
+            return Err(AppError::BadRequest);
+        }
+    };
+
+    let format = match format {
+        Some(format) => match format.parse::<CaptionFormat>() {
+            Ok(format) => format,
+            Err(_) => {
+                info!("Invalid caption format {}", format);
+                return Err(AppError::BadRequest);
+            }
+        },
+        None => detect_caption_format(&content),
+    };
+
+    let content = match caption_file_to_vtt(&content, format) {
+        Some(content) => content,
+        None => {
+            info!("Invalid {:?} caption file for video {}", format, video.id);
+            return Err(AppError::BadRequest);
+        }
+    };
+
+    let label = label
+        .map(|label| sanitize_caption_label(&label))
+        .filter(|label| !label.is_empty())
+        .unwrap_or_else(|| language.clone());
+
+    let track = upsert_caption_track(
+        &connection,
+        &CaptionTrackNoId {
+            video_id: video.id,
+            language,
+            label,
+            source: CaptionSource::Upload.as_str().to_string(),
+            content,
+        },
+    )?;
+
+    Ok(ApiResponse::ok(track))
+}
+
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "caption",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>/captions/<language>")]
+pub async fn delete_captions(
+    id: String,
+    language: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if video.owner_id != user.id {
+        info!("User {} is not the owner of video {}", user.id, video.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let language = match normalize_language(&language) {
+        Some(language) => language,
+        None => return Err(AppError::BadRequest),
+    };
+
+    delete_caption_track(&connection, video.id, &language)?;
+
+    Ok(ApiMessage::ok())
+}
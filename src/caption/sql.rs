@@ -0,0 +1,62 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*};
+use diesel::prelude::*;
+
+pub fn get_caption_track(
+    connection: &PgConnection,
+    video_id: i32,
+    language: &str,
+) -> AppResult<CaptionTrack> {
+    use crate::schema::caption_tracks::dsl;
+
+    Ok(crate::schema::caption_tracks::table
+        .filter(dsl::video_id.eq(video_id))
+        .filter(dsl::language.eq(language))
+        .first::<CaptionTrack>(connection)?)
+}
+
+/// Gets the language, label and source of every caption track on a video
+pub fn get_caption_tracks_for_video(
+    connection: &PgConnection,
+    video_id: i32,
+) -> AppResult<Vec<(String, String, String)>> {
+    use crate::schema::caption_tracks::dsl;
+
+    Ok(crate::schema::caption_tracks::table
+        .filter(dsl::video_id.eq(video_id))
+        .order(dsl::language.asc())
+        .select((dsl::language, dsl::label, dsl::source))
+        .load::<(String, String, String)>(connection)?)
+}
+
+/// Inserts a caption track, replacing the video's existing track in the same language
+pub fn upsert_caption_track(
+    connection: &PgConnection,
+    track: &CaptionTrackNoId,
+) -> AppResult<CaptionTrack> {
+    use crate::schema::caption_tracks::dsl;
+
+    Ok(diesel::insert_into(crate::schema::caption_tracks::table)
+        .values(track)
+        .on_conflict((dsl::video_id, dsl::language))
+        .do_update()
+        .set(track)
+        .get_result::<CaptionTrack>(connection)?)
+}
+
+pub fn delete_caption_track(
+    connection: &PgConnection,
+    video_id: i32,
+    language: &str,
+) -> AppResult<()> {
+    use crate::schema::caption_tracks::dsl;
+
+    diesel::delete(
+        crate::schema::caption_tracks::table
+            .filter(dsl::video_id.eq(video_id))
+            .filter(dsl::language.eq(language)),
+    )
+    .execute(connection)?;
+    Ok(())
+}
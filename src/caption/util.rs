@@ -0,0 +1,268 @@
+use crate::{
+    caption::{
+        model::{CaptionFormat, CaptionSource},
+        sql::{get_caption_track, upsert_caption_track},
+    },
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::{CaptionTrackNoId, Video},
+    video::util::probe_media,
+};
+use tokio::process::Command;
+
+/// Subtitle codecs ffmpeg can convert to WebVTT. Image based subtitles can not be converted.
+const TEXT_SUBTITLE_CODECS: [&str; 7] =
+    ["subrip", "srt", "ass", "ssa", "webvtt", "mov_text", "text"];
+const MAX_LANGUAGE_LENGTH: usize = 35;
+const MAX_LABEL_LENGTH: usize = 64;
+
+/// Checks that `language` looks like a BCP 47 language tag (`en`, `pt-br`, `eng`, ...)
+/// and lowercases it so each language is only stored once per video.
+pub fn normalize_language(language: &str) -> Option<String> {
+    let language = language.trim().replace('_', "-").to_lowercase();
+    if language.is_empty() || language.len() > MAX_LANGUAGE_LENGTH {
+        return None;
+    }
+    let mut subtags = language.split('-');
+    let primary = subtags.next()?;
+    if !(2..=3).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    if !subtags.all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        return None;
+    }
+    Some(language)
+}
+
+pub fn sanitize_caption_label(label: &str) -> String {
+    let mut label: String = label.chars().filter(|c| !c.is_control()).collect();
+    label = label.trim().to_string();
+    if label.len() > MAX_LABEL_LENGTH {
+        let mut end = MAX_LABEL_LENGTH;
+        while !label.is_char_boundary(end) {
+            end -= 1;
+        }
+        label.truncate(end);
+    }
+    label
+}
+
+fn normalize_newlines(content: &str) -> String {
+    content
+        .trim_start_matches('\u{feff}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+}
+
+/// Guesses the format of an uploaded caption file from its header
+pub fn detect_caption_format(content: &str) -> CaptionFormat {
+    if content.trim_start_matches('\u{feff}').starts_with("WEBVTT") {
+        CaptionFormat::Vtt
+    } else {
+        CaptionFormat::Srt
+    }
+}
+
+/// Turns an uploaded caption file into the WebVTT file that is stored and served,
+/// returning None if it is not a valid file of that format.
+pub fn caption_file_to_vtt(content: &str, format: CaptionFormat) -> Option<String> {
+    match format {
+        CaptionFormat::Srt => srt_to_vtt(content),
+        CaptionFormat::Vtt => {
+            let content = normalize_newlines(content);
+            match content.strip_prefix("WEBVTT") {
+                Some(rest) if rest.is_empty() || rest.starts_with([' ', '\t', '\n']) => {
+                    Some(content)
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Converts an SRT file to WebVTT. SRT cues only differ in using a comma as the
+/// decimal separator of their timings, which may be followed by position hints
+/// that WebVTT does not understand.
+pub fn srt_to_vtt(content: &str) -> Option<String> {
+    let content = normalize_newlines(content);
+    let mut vtt = String::from("WEBVTT\n");
+    let mut cues = 0;
+
+    for block in content.split("\n\n") {
+        let block = block.trim_matches('\n');
+        if block.is_empty() {
+            continue;
+        }
+        let lines = block.lines().collect::<Vec<&str>>();
+        // The cue number is optional in the wild, so look for the timing line
+        let timing_index = lines.iter().position(|line| line.contains("-->"))?;
+        if timing_index > 1 {
+            return None;
+        }
+        let (start, end) = lines[timing_index].split_once("-->")?;
+        let start = srt_timestamp_to_vtt(start)?;
+        let end = srt_timestamp_to_vtt(end)?;
+
+        vtt.push('\n');
+        vtt.push_str(&format!("{} --> {}\n", start, end));
+        for line in &lines[timing_index + 1..] {
+            vtt.push_str(line);
+            vtt.push('\n');
+        }
+        cues += 1;
+    }
+
+    if cues == 0 {
+        return None;
+    }
+    Some(vtt)
+}
+
+fn srt_timestamp_to_vtt(timestamp: &str) -> Option<String> {
+    let timestamp = timestamp.split_whitespace().next()?.replace(',', ".");
+    let valid = timestamp
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == ':' || c == '.')
+        && timestamp.contains(':');
+    if valid {
+        Some(timestamp)
+    } else {
+        None
+    }
+}
+
+/// Formats a playback time in seconds as a WebVTT timestamp (`hh:mm:ss.ttt`)
+pub fn format_vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Extracts every text subtitle stream in a video into caption tracks.
+/// Only the first stream of each language is kept, and languages the owner
+/// uploaded captions for are skipped.
+pub async fn import_embedded_captions(pool: &DbPool, video: &Video) -> AppResult<()> {
+    let probe = probe_media(&video.video_path).await?;
+
+    let mut imported: Vec<String> = Vec::new();
+    for stream in probe
+        .streams
+        .iter()
+        .filter(|stream| stream.codec_type.as_deref() == Some("subtitle"))
+    {
+        if !stream
+            .codec_name
+            .as_deref()
+            .map(|codec| TEXT_SUBTITLE_CODECS.contains(&codec))
+            .unwrap_or(false)
+        {
+            info!(
+                "Skipping subtitle stream {} of video {} that is not text based",
+                stream.index, video.video_id
+            );
+            continue;
+        }
+
+        let language = stream
+            .tags
+            .as_ref()
+            .and_then(|tags| tags.language.as_deref())
+            .and_then(normalize_language)
+            .unwrap_or_else(|| String::from("und"));
+        if imported.contains(&language) {
+            continue;
+        }
+        // Captions the owner uploaded are never replaced
+        let connection = get_connection(pool)
+            .await
+            .ok_or(AppError::ServiceUnavailable)?;
+        match get_caption_track(&connection, video.id, &language) {
+            Ok(existing) if existing.source == CaptionSource::Upload.as_str() => {
+                info!(
+                    "Video {} already has uploaded {} captions, not importing subtitle stream {}",
+                    video.video_id, language, stream.index
+                );
+                continue;
+            }
+            Ok(_) | Err(AppError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+        drop(connection);
+
+        let output = Command::new("ffmpeg")
+            .args(["-v", "error", "-i", &video.video_path])
+            .args(["-map", &format!("0:{}", stream.index)])
+            .args(["-f", "webvtt", "-"])
+            .kill_on_drop(true)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(AppError::Probe(format!(
+                "ffmpeg failed to extract subtitle stream {} of video {}: {}",
+                stream.index,
+                video.video_id,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        let content = match String::from_utf8(output.stdout)
+            .ok()
+            .and_then(|content| caption_file_to_vtt(&content, CaptionFormat::Vtt))
+        {
+            Some(content) => content,
+            None => {
+                warn!(
+                    "ffmpeg produced an invalid WebVTT file for subtitle stream {} of video {}",
+                    stream.index, video.video_id
+                );
+                continue;
+            }
+        };
+
+        let connection = get_connection(pool)
+            .await
+            .ok_or(AppError::ServiceUnavailable)?;
+        upsert_caption_track(
+            &connection,
+            &CaptionTrackNoId {
+                video_id: video.id,
+                label: language.clone(),
+                language: language.clone(),
+                source: CaptionSource::Embedded.as_str().to_string(),
+                content,
+            },
+        )?;
+        imported.push(language);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::caption_file_to_vtt;
+    use crate::caption::model::CaptionFormat;
+
+    #[test]
+    fn srt_converts_to_vtt() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500 X1:10 X2:20\r\nHello\r\n\r\n\
+                   00:00:03,000 --> 00:00:04,000\r\nno cue number\r\nsecond line\r\n";
+        assert_eq!(
+            caption_file_to_vtt(srt, CaptionFormat::Srt).unwrap(),
+            "WEBVTT\n\n00:00:01.000 --> 00:00:02.500\nHello\n\n\
+             00:00:03.000 --> 00:00:04.000\nno cue number\nsecond line\n"
+        );
+    }
+
+    #[test]
+    fn srt_without_cues_is_rejected() {
+        assert!(caption_file_to_vtt("", CaptionFormat::Srt).is_none());
+        assert!(caption_file_to_vtt("just some text", CaptionFormat::Srt).is_none());
+        assert!(caption_file_to_vtt("1\n00:00:01 --> soon\nHi\n", CaptionFormat::Srt).is_none());
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChapterInfo {
+    /// Playback time in seconds the chapter starts at
+    pub start: f64,
+    pub title: String,
+}
+
+/// A chapter as reported by `ffprobe -show_chapters`
+#[derive(Debug, Deserialize)]
+pub struct ProbedChapter {
+    pub start_time: String,
+    #[serde(default)]
+    pub tags: ProbedChapterTags,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProbedChapterTags {
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProbedChapters {
+    #[serde(default)]
+    pub chapters: Vec<ProbedChapter>,
+}
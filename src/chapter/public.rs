@@ -0,0 +1,89 @@
+use crate::{
+    auth::util::get_user_from_cookies,
+    chapter::{
+        model::ChapterInfo,
+        sql::{get_chapters_for_video, set_video_chapters},
+        util::{chapters_to_vtt, validate_chapters},
+    },
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::Chapter,
+    response::{ApiResponse, ApiResult},
+    video::{
+        sql::get_video_by_video_id,
+        util::{get_video_access, VideoAccess},
+    },
+};
+use rocket::http::{ContentType, CookieJar};
+use rocket::serde::json::Json;
+use rocket::State;
+
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "chapter",
+    responses((status = 200, description = "The chapters as WebVTT", body = String, content_type = "text/vtt"))
+)]
+#[get("/<id>/chapters.vtt")]
+pub async fn get_chapters_vtt(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> AppResult<(ContentType, String)> {
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if get_video_access(&connection, &video, user.as_ref(), cookies)? != VideoAccess::Allowed {
+        return Err(AppError::NotFound);
+    }
+
+    let chapters = get_chapters_for_video(&connection, video.id)?;
+
+    Ok((
+        ContentType::new("text", "vtt"),
+        chapters_to_vtt(&chapters, video.video_length),
+    ))
+}
+
+/// Replaces all chapters of a video with the given list
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "chapter",
+    request_body = Vec<ChapterInfo>,
+    responses((status = 200, body = ChapterListResponse))
+)]
+#[post("/<id>/chapters", data = "<chapters>", format = "json")]
+pub async fn set_chapters(
+    id: String,
+    chapters: Json<Vec<ChapterInfo>>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<Chapter>> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if video.owner_id != user.id {
+        info!("User {} is not the owner of video {}", user.id, video.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let chapters = match validate_chapters(video.id, video.video_length, &chapters) {
+        Some(chapters) => chapters,
+        None => return Err(AppError::BadRequest),
+    };
+
+    set_video_chapters(&connection, video.id, &chapters)?;
+
+    let chapters = get_chapters_for_video(&connection, video.id)?;
+
+    Ok(ApiResponse::ok(chapters))
+}
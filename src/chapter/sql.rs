@@ -0,0 +1,56 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*};
+use diesel::prelude::*;
+
+/// Gets a video's chapters in playback order
+pub fn get_chapters_for_video(connection: &PgConnection, video_id: i32) -> AppResult<Vec<Chapter>> {
+    use crate::schema::chapters::dsl;
+
+    Ok(crate::schema::chapters::table
+        .filter(dsl::video_id.eq(video_id))
+        .order(dsl::start_seconds.asc())
+        .load::<Chapter>(connection)?)
+}
+
+/// Replaces all chapters of a video
+pub fn set_video_chapters(
+    connection: &PgConnection,
+    video_id: i32,
+    chapters: &[ChapterNoId],
+) -> AppResult<()> {
+    use crate::schema::chapters::dsl;
+
+    connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(crate::schema::chapters::table.filter(dsl::video_id.eq(video_id)))
+            .execute(connection)?;
+        diesel::insert_into(crate::schema::chapters::table)
+            .values(chapters)
+            .execute(connection)
+    })?;
+    Ok(())
+}
+
+/// Stores chapters for a video that has none, returning whether they were stored.
+/// Chapters the owner already set are kept.
+pub fn insert_chapters_if_none(
+    connection: &PgConnection,
+    video_id: i32,
+    chapters: &[ChapterNoId],
+) -> AppResult<bool> {
+    use crate::schema::chapters::dsl;
+
+    Ok(connection.transaction::<_, diesel::result::Error, _>(|| {
+        let existing = diesel::select(diesel::dsl::exists(
+            crate::schema::chapters::table.filter(dsl::video_id.eq(video_id)),
+        ))
+        .get_result::<bool>(connection)?;
+        if existing {
+            return Ok(false);
+        }
+        diesel::insert_into(crate::schema::chapters::table)
+            .values(chapters)
+            .execute(connection)?;
+        Ok(true)
+    })?)
+}
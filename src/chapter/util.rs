@@ -0,0 +1,157 @@
+use crate::{
+    caption::util::format_vtt_timestamp,
+    chapter::{
+        model::{ChapterInfo, ProbedChapters},
+        sql::insert_chapters_if_none,
+    },
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::{Chapter, ChapterNoId, Video},
+    video::util::valid_playback_time,
+};
+use sanitize_html::rules::predefined::DEFAULT;
+use sanitize_html::sanitize_str;
+use tokio::process::Command;
+
+const MAX_CHAPTERS: usize = 256;
+const MAX_TITLE_LENGTH: usize = 256;
+
+fn sanitize_chapter_title(title: &str) -> Option<String> {
+    let mut title = match sanitize_str(&DEFAULT, title) {
+        Ok(title) => title.trim().to_string(),
+        Err(e) => {
+            warn!("Failed to sanitize chapter title with error: {}", e);
+            return None;
+        }
+    };
+    if title.is_empty() {
+        return None;
+    }
+    if title.len() > MAX_TITLE_LENGTH {
+        let mut end = MAX_TITLE_LENGTH;
+        while !title.is_char_boundary(end) {
+            end -= 1;
+        }
+        title.truncate(end);
+    }
+    Some(title)
+}
+
+/// Validates chapters for a video of `video_length` seconds, returning them sorted by
+/// start time. Fails if a start time is out of range, repeated, or a title is empty.
+pub fn validate_chapters(
+    video_id: i32,
+    video_length: f64,
+    chapters: &[ChapterInfo],
+) -> Option<Vec<ChapterNoId>> {
+    if chapters.len() > MAX_CHAPTERS {
+        info!("Too many chapters ({})", chapters.len());
+        return None;
+    }
+    let mut validated = Vec::with_capacity(chapters.len());
+    for chapter in chapters {
+        if !valid_playback_time(chapter.start, video_length) {
+            info!("Invalid chapter start {}", chapter.start);
+            return None;
+        }
+        validated.push(ChapterNoId {
+            video_id,
+            start_seconds: chapter.start,
+            title: sanitize_chapter_title(&chapter.title)?,
+        });
+    }
+    validated.sort_by(|a, b| {
+        a.start_seconds
+            .partial_cmp(&b.start_seconds)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    if validated
+        .windows(2)
+        .any(|pair| pair[0].start_seconds == pair[1].start_seconds)
+    {
+        info!("Chapters with the same start time");
+        return None;
+    }
+    Some(validated)
+}
+
+/// Makes a WebVTT chapters track. Each chapter ends where the next one starts,
+/// and the last one at the end of the video.
+pub fn chapters_to_vtt(chapters: &[Chapter], video_length: f64) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        let end = match chapters.get(i + 1) {
+            Some(next) => next.start_seconds,
+            // Videos with an unknown length (negative) get a short last cue
+            None if video_length > chapter.start_seconds => video_length,
+            None => chapter.start_seconds + 1.0,
+        };
+        vtt.push_str(&format!(
+            "\n{}\n{} --> {}\n{}\n",
+            i + 1,
+            format_vtt_timestamp(chapter.start_seconds),
+            format_vtt_timestamp(end),
+            chapter.title
+        ));
+    }
+    vtt
+}
+
+/// Imports the chapters stored in a video file's container metadata, if any,
+/// unless the video already has chapters
+pub async fn import_chapters(pool: &DbPool, video: &Video) -> AppResult<()> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-print_format", "json", "-show_chapters"])
+        .arg(&video.video_path)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(AppError::Probe(format!(
+            "ffprobe failed to read chapters of video {}: {}",
+            video.video_id,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let probed = serde_json::from_slice::<ProbedChapters>(&output.stdout)
+        .map_err(|e| AppError::Probe(format!("invalid ffprobe chapters ({})", e)))?;
+    if probed.chapters.is_empty() {
+        return Ok(());
+    }
+
+    let chapters = probed
+        .chapters
+        .iter()
+        .enumerate()
+        .filter_map(|(i, chapter)| {
+            Some(ChapterInfo {
+                start: chapter.start_time.parse::<f64>().ok()?,
+                title: chapter
+                    .tags
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+            })
+        })
+        .collect::<Vec<ChapterInfo>>();
+
+    match validate_chapters(video.id, video.video_length, &chapters) {
+        Some(chapters) => {
+            let connection = get_connection(pool)
+                .await
+                .ok_or(AppError::ServiceUnavailable)?;
+            if !insert_chapters_if_none(&connection, video.id, &chapters)? {
+                info!(
+                    "Video {} already has chapters, not importing them",
+                    video.video_id
+                );
+            }
+        }
+        None => warn!(
+            "Video {} has chapter metadata that could not be imported",
+            video.video_id
+        ),
+    }
+    Ok(())
+}
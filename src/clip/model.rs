@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClipRequest {
+    /// Playback time in seconds the clip starts at
+    pub start: f64,
+    /// Playback time in seconds the clip ends at
+    pub end: f64,
+    /// Trims the video itself instead of creating a new video from the clip
+    #[serde(default)]
+    pub replace: bool,
+    /// Name of the new video, defaults to the source video's name
+    pub name: Option<String>,
+}
+
+/// How a clip is cut out of its source video
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipMethod {
+    /// Copies the streams as they are, only possible when the clip starts on a keyframe
+    StreamCopy,
+    /// Re-encodes the clip to H.264 and AAC in an MP4 file
+    Reencode,
+}
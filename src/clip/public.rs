@@ -0,0 +1,156 @@
+use crate::{
+    auth::util::get_user_from_cookies,
+    clip::model::ClipRequest,
+    db::{get_connection, DbPool},
+    error::AppError,
+    job::{model::JobKind, util::enqueue_job},
+    models::{Video, VideoNoId},
+    response::{ApiResponse, ApiResult},
+    tag::sql::{get_tags_for_video, set_video_tags},
+    video::{
+        model::VideoStatus,
+        sql::{generate_new_video_id, get_video_by_video_id, insert_new_video, set_video_status},
+        util::{clean_original_filename, get_filename_ending, valid_video_filename_ending},
+    },
+};
+use diesel::Connection;
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use rocket::State;
+use sanitize_html::rules::predefined::DEFAULT;
+use sanitize_html::sanitize_str;
+
+/// Cuts a clip out of a video, either into a new video or trimming the video itself.
+/// The returned video has the `processing` status until the clip is ready.
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "clip",
+    request_body = ClipRequest,
+    responses((status = 200, body = VideoResponse))
+)]
+#[post("/<id>/clip", data = "<clip>", format = "json")]
+pub async fn clip_video(
+    id: String,
+    clip: Json<ClipRequest>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Video> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let source = get_video_by_video_id(&connection, &id)?;
+
+    if source.owner_id != user.id {
+        info!("User {} is not the owner of video {}", user.id, source.id);
+        return Err(AppError::Forbidden);
+    }
+
+    if source.status != VideoStatus::Ready.as_str() {
+        info!("Video {} is not ready to be clipped", source.id);
+        return Err(AppError::Conflict);
+    }
+
+    let valid_range = clip.start.is_finite()
+        && clip.end.is_finite()
+        && clip.start >= 0.0
+        && clip.start < clip.end
+        && (source.video_length < 0.0 || clip.end <= source.video_length);
+    if !valid_range {
+        info!("Invalid clip range {} to {}", clip.start, clip.end);
+        return Err(AppError::BadRequest);
+    }
+
+    if clip.replace {
+        let target = connection.transaction::<_, AppError, _>(|| {
+            set_video_status(&connection, source.id, VideoStatus::Processing)?;
+            enqueue_job(
+                &connection,
+                &JobKind::ClipVideo {
+                    source_id: source.id,
+                    target_id: source.id,
+                    start: clip.start,
+                    end: clip.end,
+                },
+            )?;
+            get_video_by_video_id(&connection, &source.video_id)
+        })?;
+        return Ok(ApiResponse::ok(target));
+    }
+
+    let name = match &clip.name {
+        Some(name) => match sanitize_str(&DEFAULT, name) {
+            Ok(name) => {
+                let mut name = name.replace("..", "").replace('/', "");
+                if name.len() > 128 {
+                    info!("Name too long. Cutting off at 128 characters");
+                    name.truncate(128);
+                }
+                name
+            }
+            Err(e) => {
+                return Err(AppError::Internal(format!(
+                    "failed to sanitize name {} ({})",
+                    name, e
+                )));
+            }
+        },
+        None => source.video_name.clone(),
+    };
+    if name.is_empty() {
+        return Err(AppError::BadRequest);
+    }
+    // The clip job gives the file its real ending once it knows how the clip is cut
+    let name = if valid_video_filename_ending(&name) {
+        name
+    } else {
+        format!(
+            "{}.{}",
+            name,
+            get_filename_ending(&source.video_name).unwrap_or_else(|| String::from("mp4"))
+        )
+    };
+
+    let video_id = generate_new_video_id(&connection)?;
+    let clip_video = VideoNoId {
+        video_url: format!("/api/video/{}/{}", video_id, name),
+        video_path: format!("videos/{}/{}.clip", user.id, video_id),
+        original_filename: clean_original_filename(&name),
+        video_id,
+        video_name: name,
+        video_length: clip.end - clip.start,
+        video_desc: source.video_desc.clone(),
+        owner_id: user.id,
+        thumbnail_path: None,
+        visibility: source.visibility.clone(),
+        password_hash: source.password_hash.clone(),
+        file_size: 0,
+        status: VideoStatus::Processing.as_str().to_string(),
+        folder_id: source.folder_id,
+        source_video_id: Some(source.id),
+        downloads_enabled: source.downloads_enabled,
+        sha256: None,
+    };
+    let target = connection.transaction::<_, AppError, _>(|| {
+        let target = insert_new_video(&connection, &clip_video)?;
+
+        let tags = get_tags_for_video(&connection, source.id)?;
+        set_video_tags(&connection, target.id, user.id, &tags)?;
+
+        enqueue_job(
+            &connection,
+            &JobKind::ClipVideo {
+                source_id: source.id,
+                target_id: target.id,
+                start: clip.start,
+                end: clip.end,
+            },
+        )?;
+        Ok(target)
+    })?;
+
+    Ok(ApiResponse::ok(target))
+}
@@ -0,0 +1,275 @@
+use crate::{
+    audio::util::remove_cached_audio,
+    clip::model::ClipMethod,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    event::{model::VideoEventKind, util::VideoEvents},
+    job::{model::JobKind, util::enqueue_job},
+    media::{
+        sql::reference_media_blob,
+        util::{hash_file, media_blob_path, release_video_file, MEDIA_BLOB_FOLDER},
+    },
+    models::{MediaBlobNoId, Video},
+    util::remove_file_if_exists,
+    video::{
+        model::VideoStatus,
+        sql::{get_video_by_id, set_video_status, update_video_media},
+        util::{get_filename_ending, probe_video_length, with_filename_ending},
+    },
+};
+use diesel::{Connection, PgConnection};
+use rocket::tokio::fs;
+use std::process::Stdio;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+};
+
+/// How far from the requested start a keyframe may be for the clip to be stream copied
+const KEYFRAME_TOLERANCE_SECONDS: f64 = 0.05;
+
+/// Whether `start` falls on a keyframe of the video at `path`, so the clip can be stream copied
+async fn starts_on_keyframe(path: &str, start: f64) -> bool {
+    if start == 0.0 {
+        return true;
+    }
+    let output = match Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-skip_frame",
+            "nokey",
+        ])
+        .args(["-show_entries", "frame=pts_time", "-of", "csv=p=0"])
+        .args(["-read_intervals", &format!("{}%+1", start)])
+        .arg(path)
+        .kill_on_drop(true)
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => output,
+        Ok(_) | Err(_) => {
+            warn!("Failed to probe keyframes of {}", path);
+            return false;
+        }
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().trim_end_matches(',').parse::<f64>().ok())
+        .any(|time| (time - start).abs() <= KEYFRAME_TOLERANCE_SECONDS)
+}
+
+/// Cuts `start`..`end` out of `source` into `output`, calling `on_progress` with
+/// each whole percent ffmpeg gets further
+async fn cut_video<F: Fn(u8)>(
+    source: &str,
+    output: &str,
+    start: f64,
+    end: f64,
+    method: ClipMethod,
+    on_progress: F,
+) -> AppResult<()> {
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-v", "error", "-y", "-ss", &start.to_string(), "-i", source])
+        .args(["-t", &(end - start).to_string()]);
+    match method {
+        ClipMethod::StreamCopy => {
+            command.args(["-map", "0", "-c", "copy", "-avoid_negative_ts", "make_zero"])
+        }
+        ClipMethod::Reencode => command
+            .args(["-map", "0:v:0", "-map", "0:a?"])
+            .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "20"])
+            .args(["-c:a", "aac", "-movflags", "+faststart"]),
+    };
+    let mut child = command
+        .args(["-progress", "pipe:1", "-nostats"])
+        .arg(output)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // A cancelled clip job stops ffmpeg with it
+        .kill_on_drop(true)
+        .spawn()?;
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut reported = 0;
+        while let Ok(Some(line)) = lines.next_line().await {
+            let written_seconds = match line
+                .strip_prefix("out_time_us=")
+                .and_then(|micros| micros.parse::<f64>().ok())
+            {
+                Some(micros) => micros / 1_000_000.0,
+                None => continue,
+            };
+            let percent = (written_seconds / (end - start) * 100.0).clamp(0.0, 100.0) as u8;
+            if percent > reported {
+                reported = percent;
+                on_progress(percent);
+            }
+        }
+    }
+    let result = child.wait_with_output().await?;
+    if !result.status.success() {
+        return Err(AppError::Probe(format!(
+            "ffmpeg failed to clip {}: {}",
+            source,
+            String::from_utf8_lossy(&result.stderr)
+        )));
+    }
+    Ok(())
+}
+
+/// Hashes the clip at `temp_path` and moves it to the blob path of its content,
+/// unless a file with that content is already stored there.
+/// Returns the blob path, the hash and the size of the file.
+async fn store_clip(temp_path: &str, ending: &str) -> AppResult<(String, String, i64)> {
+    let sha256 = hash_file(temp_path, None).await?;
+    let file_size = fs::metadata(temp_path).await?.len() as i64;
+    let blob_path = media_blob_path(&sha256, ending);
+    if !std::path::Path::new(&blob_path).exists() {
+        fs::create_dir_all(MEDIA_BLOB_FOLDER).await?;
+        fs::rename(temp_path, &blob_path).await?;
+    }
+    Ok((blob_path, sha256, file_size))
+}
+
+fn clip_failed() -> VideoEventKind {
+    VideoEventKind::Failed {
+        reason: String::from("the clip could not be written"),
+    }
+}
+
+/// Marks the target of a clip job that will not finish, it would stay processing otherwise.
+/// A trimmed video goes back to ready since its original file is still there.
+pub fn fail_clip(
+    connection: &PgConnection,
+    events: &VideoEvents,
+    source_id: i32,
+    target_id: i32,
+) -> AppResult<()> {
+    let target = match get_video_by_id(connection, target_id) {
+        Ok(target) => target,
+        Err(AppError::NotFound) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let status = if source_id == target_id {
+        VideoStatus::Ready
+    } else {
+        VideoStatus::Failed
+    };
+    set_video_status(connection, target.id, status)?;
+    events.publish(&target, clip_failed());
+    Ok(())
+}
+
+/// Cuts `start`..`end` out of `source` into `target`'s file, then marks `target` as ready.
+/// `target` is either a new video made for the clip, or `source` itself when trimming.
+///
+/// The clip is written to a temporary file first, then stored by its content,
+/// so a failed trim leaves the original untouched. `target` stays processing when this fails, the job is
+/// retried and `fail_clip` is called once it runs out of attempts.
+pub async fn run_clip_job(
+    pool: &DbPool,
+    events: &VideoEvents,
+    source: &Video,
+    target: &Video,
+    start: f64,
+    end: f64,
+) -> AppResult<()> {
+    let replace = source.id == target.id;
+    let method = if starts_on_keyframe(&source.video_path, start).await {
+        ClipMethod::StreamCopy
+    } else {
+        ClipMethod::Reencode
+    };
+    let ending = match method {
+        ClipMethod::StreamCopy => {
+            get_filename_ending(&source.video_path).unwrap_or_else(|| String::from("mp4"))
+        }
+        ClipMethod::Reencode => String::from("mp4"),
+    };
+    info!(
+        "Clipping video {} ({}s to {}s) into video {} using {:?}",
+        source.video_id, start, end, target.video_id, method
+    );
+
+    let folder = format!("videos/{}", target.owner_id);
+    let temp_path = format!("{}/{}.clip.{}", folder, target.video_id, ending);
+
+    let cut = cut_video(
+        &source.video_path,
+        &temp_path,
+        start,
+        end,
+        method,
+        |percent| events.publish(target, VideoEventKind::Transcoding { percent }),
+    )
+    .await;
+    if let Err(e) = cut {
+        remove_file_if_exists(&temp_path).await;
+        return Err(e);
+    }
+
+    // The clip is stored by its content like uploads are, so it may share a file
+    let stored = store_clip(&temp_path, &ending).await;
+    remove_file_if_exists(&temp_path).await;
+    let (blob_path, sha256, file_size) = stored?;
+    if replace {
+        remove_cached_audio(source).await;
+    }
+    let video_length = match probe_video_length(&blob_path).await {
+        length if length >= 0.0 => length,
+        _ => end - start,
+    };
+
+    // A failure from here on leaves the stored file for the retry, or for the
+    // storage reconcile to find
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    let (clipped, unused_path) = connection.transaction::<_, AppError, _>(|| {
+        let blob = reference_media_blob(
+            &connection,
+            &MediaBlobNoId {
+                path: blob_path.clone(),
+                sha256: sha256.clone(),
+                file_size,
+                ref_count: 1,
+            },
+        )?;
+        let clipped = update_video_media(
+            &connection,
+            target.id,
+            &blob,
+            &format!(
+                "/api/video/{}/{}",
+                target.video_id,
+                with_filename_ending(&target.video_name, &ending)
+            ),
+            video_length,
+            VideoStatus::Ready,
+        )?;
+        // The trimmed video no longer uses its old file, which may be shared.
+        // Trimming to identical content referenced the same blob just above.
+        let unused_path = if replace {
+            release_video_file(&connection, source)?
+        } else {
+            None
+        };
+        enqueue_job(
+            &connection,
+            &JobKind::GeneratePreviews {
+                video_id: clipped.id,
+            },
+        )?;
+        Ok((clipped, unused_path))
+    })?;
+    drop(connection);
+    if let Some(path) = unused_path {
+        remove_file_if_exists(&path).await;
+    }
+    events.publish(&clipped, VideoEventKind::Ready);
+    Ok(())
+}
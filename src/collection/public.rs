@@ -0,0 +1,278 @@
+use crate::{
+    auth::util::{get_user_from_cookies, user_has_admin_permission},
+    collection::{
+        model::{CollectionContents, CollectionInfo, NewCollection},
+        sql::{
+            add_video_to_collection, delete_collection_with_id, generate_new_collection_id,
+            get_collection_by_collection_id, get_collections_for_user, get_videos_in_collection,
+            insert_collection, remove_video_from_collection, update_collection,
+        },
+    },
+    db::{get_connection, DbPool},
+    error::AppError,
+    models::{Collection, CollectionNoId},
+    response::{ApiMessage, ApiMessageResult, ApiResponse, ApiResult},
+    video::{sql::get_video_by_video_id, util::user_can_view_video},
+};
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use rocket::State;
+
+const MAX_COLLECTION_NAME_LENGTH: usize = 128;
+const MAX_COLLECTION_DESCRIPTION_LENGTH: usize = 1024;
+
+#[utoipa::path(
+    context_path = "/api/collection",
+    tag = "collection",
+    responses((status = 200, body = CollectionListResponse))
+)]
+#[get("/")]
+pub async fn list_collections(
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<Collection>> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let collections = get_collections_for_user(&connection, user.id)?;
+
+    Ok(ApiResponse::ok(collections))
+}
+
+#[utoipa::path(
+    context_path = "/api/collection",
+    tag = "collection",
+    request_body = NewCollection,
+    responses((status = 200, body = CollectionResponse))
+)]
+#[post("/", data = "<collection>", format = "json")]
+pub async fn create_collection(
+    collection: Json<NewCollection>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Collection> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut name = collection.name.trim().to_string();
+    if name.is_empty() {
+        info!("Collection name is empty");
+        return Err(AppError::BadRequest);
+    }
+    if name.len() > MAX_COLLECTION_NAME_LENGTH {
+        info!("Name too long. Cutting off at 128 characters");
+        name.truncate(MAX_COLLECTION_NAME_LENGTH);
+    }
+    let mut description = collection.description.clone().unwrap_or_default();
+    if description.len() > MAX_COLLECTION_DESCRIPTION_LENGTH {
+        info!("Description too long. Cutting off at 1024 characters");
+        description.truncate(MAX_COLLECTION_DESCRIPTION_LENGTH);
+    }
+
+    let collection = insert_collection(
+        &connection,
+        &CollectionNoId {
+            collection_id: generate_new_collection_id(&connection)?,
+            owner_id: user.id,
+            name,
+            description,
+        },
+    )?;
+
+    Ok(ApiResponse::ok(collection))
+}
+
+#[utoipa::path(
+    context_path = "/api/collection",
+    tag = "collection",
+    responses((status = 200, body = CollectionContentsResponse))
+)]
+#[get("/<id>")]
+pub async fn get_collection(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<CollectionContents> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let collection = get_collection_by_collection_id(&connection, &id)?;
+
+    if collection.owner_id != user.id && !user_has_admin_permission(&user) {
+        info!("User {} can not view collection {}", user.id, collection.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let mut videos = Vec::new();
+    for video in get_videos_in_collection(&connection, collection.id)? {
+        if user_can_view_video(&connection, &video, Some(&user))? {
+            videos.push(video);
+        }
+    }
+
+    Ok(ApiResponse::ok(CollectionContents { collection, videos }))
+}
+
+#[utoipa::path(
+    context_path = "/api/collection",
+    tag = "collection",
+    request_body = CollectionInfo,
+    responses((status = 200, body = CollectionResponse))
+)]
+#[post("/edit?<id>", data = "<info>", format = "json")]
+pub async fn edit_collection(
+    id: String,
+    info: Json<CollectionInfo>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Collection> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut collection = get_collection_by_collection_id(&connection, &id)?;
+
+    if collection.owner_id != user.id {
+        info!(
+            "User {} is not the owner of collection {}",
+            user.id, collection.id
+        );
+        return Err(AppError::Forbidden);
+    }
+
+    if let Some(name) = &info.name {
+        let mut name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::BadRequest);
+        }
+        if name.len() > MAX_COLLECTION_NAME_LENGTH {
+            info!("Name too long. Cutting off at 128 characters");
+            name.truncate(MAX_COLLECTION_NAME_LENGTH);
+        }
+        collection.name = name;
+    }
+
+    if let Some(description) = &info.description {
+        let mut description = description.clone();
+        if description.len() > MAX_COLLECTION_DESCRIPTION_LENGTH {
+            info!("Description too long. Cutting off at 1024 characters");
+            description.truncate(MAX_COLLECTION_DESCRIPTION_LENGTH);
+        }
+        collection.description = description;
+    }
+
+    let collection = update_collection(&connection, &collection)?;
+
+    Ok(ApiResponse::ok(collection))
+}
+
+#[utoipa::path(
+    context_path = "/api/collection",
+    tag = "collection",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>")]
+pub async fn delete_collection(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let collection = get_collection_by_collection_id(&connection, &id)?;
+
+    if collection.owner_id != user.id && !user_has_admin_permission(&user) {
+        info!("User did not own collection that was attempted to be deleted.");
+        return Err(AppError::Forbidden);
+    }
+
+    delete_collection_with_id(&connection, collection.id)?;
+
+    Ok(ApiMessage::ok())
+}
+
+#[utoipa::path(
+    context_path = "/api/collection",
+    tag = "collection",
+    responses((status = 200, body = ApiMessage))
+)]
+#[post("/<id>/videos/<video_id>")]
+pub async fn add_collection_video(
+    id: String,
+    video_id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let collection = get_collection_by_collection_id(&connection, &id)?;
+    if collection.owner_id != user.id {
+        return Err(AppError::Forbidden);
+    }
+
+    let video = get_video_by_video_id(&connection, &video_id)?;
+    if !user_can_view_video(&connection, &video, Some(&user))? {
+        info!("User {} can not view video {}", user.id, video.id);
+        return Err(AppError::Forbidden);
+    }
+
+    add_video_to_collection(&connection, collection.id, video.id)?;
+
+    Ok(ApiMessage::ok())
+}
+
+#[utoipa::path(
+    context_path = "/api/collection",
+    tag = "collection",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>/videos/<video_id>")]
+pub async fn remove_collection_video(
+    id: String,
+    video_id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let collection = get_collection_by_collection_id(&connection, &id)?;
+    if collection.owner_id != user.id {
+        return Err(AppError::Forbidden);
+    }
+
+    let video = get_video_by_video_id(&connection, &video_id)?;
+
+    remove_video_from_collection(&connection, collection.id, video.id)?;
+
+    Ok(ApiMessage::ok())
+}
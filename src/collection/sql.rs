@@ -0,0 +1,117 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*, util::make_random_string};
+use diesel::prelude::*;
+
+pub fn get_collection_by_collection_id(
+    connection: &PgConnection,
+    id: &String,
+) -> AppResult<Collection> {
+    Ok(crate::schema::collections::table
+        .filter(crate::schema::collections::dsl::collection_id.eq(id.to_owned()))
+        .first::<Collection>(connection)?)
+}
+
+fn get_collection_by_collection_id_no_error(
+    connection: &PgConnection,
+    id: &String,
+) -> AppResult<Option<Collection>> {
+    Ok(crate::schema::collections::table
+        .filter(crate::schema::collections::dsl::collection_id.eq(id.to_owned()))
+        .first::<Collection>(connection)
+        .optional()?)
+}
+
+pub fn get_collections_for_user(
+    connection: &PgConnection,
+    owner_id: i32,
+) -> AppResult<Vec<Collection>> {
+    Ok(crate::schema::collections::table
+        .filter(crate::schema::collections::dsl::owner_id.eq(owner_id))
+        .order(crate::schema::collections::dsl::name.asc())
+        .load::<Collection>(connection)?)
+}
+
+pub fn get_videos_in_collection(
+    connection: &PgConnection,
+    collection_id: i32,
+) -> AppResult<Vec<Video>> {
+    use crate::schema::{collection_videos, videos};
+
+    Ok(videos::table
+        .inner_join(collection_videos::table)
+        .filter(collection_videos::dsl::collection_id.eq(collection_id))
+        .filter(videos::dsl::deleted_at.is_null())
+        .select(videos::all_columns)
+        .order(collection_videos::dsl::id.asc())
+        .load::<Video>(connection)?)
+}
+
+pub fn insert_collection(
+    connection: &PgConnection,
+    collection: &CollectionNoId,
+) -> AppResult<Collection> {
+    Ok(diesel::insert_into(crate::schema::collections::table)
+        .values(collection)
+        .get_result::<Collection>(connection)?)
+}
+
+pub fn update_collection(
+    connection: &PgConnection,
+    collection: &Collection,
+) -> AppResult<Collection> {
+    Ok(diesel::update(
+        crate::schema::collections::table
+            .filter(crate::schema::collections::dsl::id.eq(collection.id)),
+    )
+    .set(collection)
+    .get_result::<Collection>(connection)?)
+}
+
+pub fn delete_collection_with_id(connection: &PgConnection, id: i32) -> AppResult<()> {
+    diesel::delete(
+        crate::schema::collections::table.filter(crate::schema::collections::dsl::id.eq(id)),
+    )
+    .execute(connection)?;
+    Ok(())
+}
+
+pub fn add_video_to_collection(
+    connection: &PgConnection,
+    collection_id: i32,
+    video_id: i32,
+) -> AppResult<()> {
+    diesel::insert_into(crate::schema::collection_videos::table)
+        .values(&CollectionVideoNoId {
+            collection_id,
+            video_id,
+        })
+        .on_conflict_do_nothing()
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn remove_video_from_collection(
+    connection: &PgConnection,
+    collection_id: i32,
+    video_id: i32,
+) -> AppResult<()> {
+    use crate::schema::collection_videos::dsl;
+
+    diesel::delete(
+        crate::schema::collection_videos::table
+            .filter(dsl::collection_id.eq(collection_id))
+            .filter(dsl::video_id.eq(video_id)),
+    )
+    .execute(connection)?;
+    Ok(())
+}
+
+/// Generates a new collection id that does not exist in the database
+pub fn generate_new_collection_id(connection: &PgConnection) -> AppResult<String> {
+    let mut collection_id = make_random_string(32);
+    while get_collection_by_collection_id_no_error(connection, &collection_id)?.is_some() {
+        collection_id = make_random_string(32);
+    }
+    Ok(collection_id)
+}
@@ -0,0 +1,26 @@
+use crate::models::Comment;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewComment {
+    /// Playback time in seconds the comment is pinned to
+    pub timestamp: f64,
+    pub body: String,
+    /// The id of the comment this is a reply to
+    pub parent: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CommentInfo {
+    pub timestamp: Option<f64>,
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentWithAuthor {
+    #[serde(flatten)]
+    pub comment: Comment,
+    /// The author's display name
+    pub author: String,
+}
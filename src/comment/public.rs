@@ -0,0 +1,247 @@
+use crate::{
+    auth::util::{get_user_from_cookies, user_has_admin_permission},
+    comment::{
+        model::{CommentInfo, CommentWithAuthor, NewComment},
+        sql::{
+            delete_comment_with_id, get_comment_by_id, get_comments_for_video, insert_comment,
+            update_comment,
+        },
+        util::sanitize_comment_body,
+    },
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::{Comment, CommentNoId, User, Video},
+    response::{ApiMessage, ApiMessageResult, ApiResponse, ApiResult},
+    video::{
+        sql::{get_video_by_id, get_video_by_video_id},
+        util::{get_video_access, valid_playback_time, VideoAccess},
+    },
+};
+use diesel::PgConnection;
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use rocket::State;
+
+/// Comments can be seen and written by anyone who can watch the video
+fn can_comment_on(
+    connection: &PgConnection,
+    video: &Video,
+    user: Option<&User>,
+    cookies: &CookieJar<'_>,
+) -> AppResult<bool> {
+    Ok(get_video_access(connection, video, user, cookies)? == VideoAccess::Allowed)
+}
+
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "comment",
+    responses((status = 200, body = CommentListResponse))
+)]
+#[get("/<id>/comments")]
+pub async fn get_comments(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<CommentWithAuthor>> {
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if !can_comment_on(&connection, &video, user.as_ref(), cookies)? {
+        return match user {
+            Some(_) => Err(AppError::Forbidden),
+            None => Err(AppError::Unauthorized),
+        };
+    }
+
+    let comments = get_comments_for_video(&connection, video.id)?;
+
+    Ok(ApiResponse::ok(comments))
+}
+
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "comment",
+    request_body = NewComment,
+    responses((status = 200, body = CommentResponse))
+)]
+#[post("/<id>/comments", data = "<comment>", format = "json")]
+pub async fn add_comment(
+    id: String,
+    comment: Json<NewComment>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Comment> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if !can_comment_on(&connection, &video, Some(&user), cookies)? {
+        info!("User {} can not comment on video {}", user.id, video.id);
+        return Err(AppError::Forbidden);
+    }
+
+    if !valid_playback_time(comment.timestamp, video.video_length) {
+        info!("Invalid comment timestamp {}", comment.timestamp);
+        return Err(AppError::BadRequest);
+    }
+
+    let body = match sanitize_comment_body(&comment.body) {
+        Some(body) => body,
+        None => return Err(AppError::BadRequest),
+    };
+
+    if let Some(parent_id) = comment.parent {
+        match get_comment_by_id(&connection, parent_id) {
+            Ok(parent) if parent.video_id == video.id => (),
+            Ok(_) | Err(AppError::NotFound) => {
+                info!("Invalid parent comment {}", parent_id);
+                return Err(AppError::BadRequest);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let comment = insert_comment(
+        &connection,
+        &CommentNoId {
+            video_id: video.id,
+            author_id: user.id,
+            timestamp_seconds: comment.timestamp,
+            body,
+            parent_id: comment.parent,
+        },
+    )?;
+
+    Ok(ApiResponse::ok(comment))
+}
+
+#[utoipa::path(
+    context_path = "/api/comment",
+    tag = "comment",
+    request_body = CommentInfo,
+    responses((status = 200, body = CommentResponse))
+)]
+#[post("/edit?<id>", data = "<info>", format = "json")]
+pub async fn edit_comment(
+    id: i32,
+    info: Json<CommentInfo>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Comment> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut comment = get_comment_by_id(&connection, id)?;
+
+    if comment.author_id != user.id {
+        info!(
+            "User {} is not the author of comment {}",
+            user.id, comment.id
+        );
+        return Err(AppError::Forbidden);
+    }
+
+    if let Some(timestamp) = info.timestamp {
+        let video = get_video_by_id(&connection, comment.video_id)?;
+        if !valid_playback_time(timestamp, video.video_length) {
+            info!("Invalid comment timestamp {}", timestamp);
+            return Err(AppError::BadRequest);
+        }
+        comment.timestamp_seconds = timestamp;
+    }
+
+    if let Some(body) = &info.body {
+        comment.body = match sanitize_comment_body(body) {
+            Some(body) => body,
+            None => return Err(AppError::BadRequest),
+        };
+    }
+
+    let comment = update_comment(&connection, &comment)?;
+
+    Ok(ApiResponse::ok(comment))
+}
+
+#[utoipa::path(
+    context_path = "/api/comment",
+    tag = "comment",
+    responses((status = 200, body = CommentResponse))
+)]
+#[post("/<id>/resolve?<resolved>")]
+pub async fn resolve_comment(
+    id: i32,
+    resolved: Option<bool>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Comment> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut comment = get_comment_by_id(&connection, id)?;
+
+    let video = get_video_by_id(&connection, comment.video_id)?;
+
+    // The author and the video's owner can resolve a comment
+    if comment.author_id != user.id && video.owner_id != user.id {
+        info!("User {} can not resolve comment {}", user.id, comment.id);
+        return Err(AppError::Forbidden);
+    }
+
+    comment.resolved = resolved.unwrap_or(true);
+
+    let comment = update_comment(&connection, &comment)?;
+
+    Ok(ApiResponse::ok(comment))
+}
+
+#[utoipa::path(
+    context_path = "/api/comment",
+    tag = "comment",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>")]
+pub async fn delete_comment(
+    id: i32,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let comment = get_comment_by_id(&connection, id)?;
+
+    let video = get_video_by_id(&connection, comment.video_id)?;
+
+    if comment.author_id != user.id
+        && video.owner_id != user.id
+        && !user_has_admin_permission(&user)
+    {
+        info!("User {} can not delete comment {}", user.id, comment.id);
+        return Err(AppError::Forbidden);
+    }
+
+    delete_comment_with_id(&connection, comment.id)?;
+
+    Ok(ApiMessage::ok())
+}
@@ -0,0 +1,52 @@
+extern crate diesel;
+
+use crate::{comment::model::CommentWithAuthor, error::AppResult, models::*};
+use diesel::prelude::*;
+
+pub fn get_comment_by_id(connection: &PgConnection, id: i32) -> AppResult<Comment> {
+    Ok(crate::schema::comments::table
+        .filter(crate::schema::comments::dsl::id.eq(id))
+        .first::<Comment>(connection)?)
+}
+
+/// Gets all comments on a video ordered by playback time, replies included
+pub fn get_comments_for_video(
+    connection: &PgConnection,
+    video_id: i32,
+) -> AppResult<Vec<CommentWithAuthor>> {
+    use crate::schema::{comments, users};
+
+    Ok(comments::table
+        .inner_join(users::table)
+        .filter(comments::dsl::video_id.eq(video_id))
+        .order((
+            comments::dsl::timestamp_seconds.asc(),
+            comments::dsl::created_at.asc(),
+        ))
+        .select((comments::all_columns, users::dsl::displayname))
+        .load::<(Comment, String)>(connection)?
+        .into_iter()
+        .map(|(comment, author)| CommentWithAuthor { comment, author })
+        .collect())
+}
+
+pub fn insert_comment(connection: &PgConnection, comment: &CommentNoId) -> AppResult<Comment> {
+    Ok(diesel::insert_into(crate::schema::comments::table)
+        .values(comment)
+        .get_result::<Comment>(connection)?)
+}
+
+pub fn update_comment(connection: &PgConnection, comment: &Comment) -> AppResult<Comment> {
+    Ok(diesel::update(
+        crate::schema::comments::table.filter(crate::schema::comments::dsl::id.eq(comment.id)),
+    )
+    .set(comment)
+    .get_result::<Comment>(connection)?)
+}
+
+/// Deletes a comment and all replies to it
+pub fn delete_comment_with_id(connection: &PgConnection, id: i32) -> AppResult<()> {
+    diesel::delete(crate::schema::comments::table.filter(crate::schema::comments::dsl::id.eq(id)))
+        .execute(connection)?;
+    Ok(())
+}
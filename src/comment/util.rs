@@ -0,0 +1,27 @@
+use sanitize_html::rules::predefined::DEFAULT;
+use sanitize_html::sanitize_str;
+
+const MAX_COMMENT_LENGTH: usize = 4096;
+
+/// Strips any HTML from a comment body, returning None if it is empty afterwards
+pub fn sanitize_comment_body(body: &str) -> Option<String> {
+    let mut body = match sanitize_str(&DEFAULT, body) {
+        Ok(body) => body.trim().to_string(),
+        Err(e) => {
+            warn!("Failed to sanitize comment with error: {}", e);
+            return None;
+        }
+    };
+    if body.is_empty() {
+        return None;
+    }
+    if body.len() > MAX_COMMENT_LENGTH {
+        info!("Comment too long. Cutting off at 4096 characters");
+        let mut end = MAX_COMMENT_LENGTH;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+    }
+    Some(body)
+}
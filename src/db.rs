@@ -0,0 +1,122 @@
+use crate::unwrap_or_return_result;
+use diesel::{
+    connection::SimpleConnection,
+    r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection},
+    PgConnection,
+};
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+use std::{
+    env,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+
+const DEFAULT_POOL_SIZE: u32 = 10;
+const DEFAULT_POOL_TIMEOUT_SECONDS: u64 = 5;
+const DEFAULT_IDLE_TIMEOUT_SECONDS: u64 = 300;
+
+/// Reads an environment variable, falling back to `default` when it is unset or invalid
+pub fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => match value.parse() {
+            Ok(value) => value,
+            Err(_) => {
+                warn!("Ignoring invalid value {} for {}", value, name);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// Runs every session in UTC, so `CURRENT_TIMESTAMP` defaults and timestamps
+/// written from Rust agree
+#[derive(Debug)]
+struct UtcSession;
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for UtcSession {
+    fn on_acquire(&self, connection: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        connection
+            .batch_execute("SET TIME ZONE 'UTC'")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Creates the connection pool shared by every request and background task.
+///
+/// The pool is configured with `DATABASE_POOL_SIZE`, `DATABASE_POOL_TIMEOUT_SECONDS`
+/// (how long to wait for a free connection) and `DATABASE_IDLE_TIMEOUT_SECONDS`.
+pub fn create_pool() -> Option<DbPool> {
+    let database_url = unwrap_or_return_result!(env::var("DATABASE_URL"), "Database URL not set.");
+    Some(unwrap_or_return_result!(
+        Pool::builder()
+            .max_size(env_or("DATABASE_POOL_SIZE", DEFAULT_POOL_SIZE))
+            .connection_timeout(Duration::from_secs(env_or(
+                "DATABASE_POOL_TIMEOUT_SECONDS",
+                DEFAULT_POOL_TIMEOUT_SECONDS
+            )))
+            .idle_timeout(Some(Duration::from_secs(env_or(
+                "DATABASE_IDLE_TIMEOUT_SECONDS",
+                DEFAULT_IDLE_TIMEOUT_SECONDS
+            ))))
+            .connection_customizer(Box::new(UtcSession))
+            .build(ConnectionManager::<PgConnection>::new(database_url)),
+        "Error creating database connection pool!"
+    ))
+}
+
+/// Gets a connection from the pool without blocking the async runtime
+pub async fn get_connection(pool: &DbPool) -> Option<DbConn> {
+    let pool = pool.clone();
+    match rocket::tokio::task::spawn_blocking(move || pool.get()).await {
+        Ok(Ok(connection)) => Some(DbConn(connection)),
+        Ok(Err(e)) => {
+            warn!("Failed to get connection from pool (error {})", e);
+            None
+        }
+        Err(e) => {
+            warn!("Failed to wait for connection from pool (error {})", e);
+            None
+        }
+    }
+}
+
+/// A pooled database connection, returned to the pool when dropped.
+///
+/// Requests fail with 503 Service Unavailable when no connection frees up in time.
+pub struct DbConn(pub PooledConnection<ConnectionManager<PgConnection>>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DbConn {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ()> {
+        let pool = match request.rocket().state::<DbPool>() {
+            Some(pool) => pool,
+            None => return Outcome::Failure((Status::InternalServerError, ())),
+        };
+        match get_connection(pool).await {
+            Some(connection) => Outcome::Success(connection),
+            None => Outcome::Failure((Status::ServiceUnavailable, ())),
+        }
+    }
+}
+
+impl Deref for DbConn {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        &self.0
+    }
+}
+
+impl DerefMut for DbConn {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        &mut self.0
+    }
+}
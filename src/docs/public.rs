@@ -0,0 +1,30 @@
+use crate::docs::util::ApiDoc;
+use rocket::response::content::{RawHtml, RawJson};
+use utoipa::OpenApi;
+
+/// Redoc is pinned in the frontend's package.json and served from `static/vendor`,
+/// so no third-party script runs on the API's origin
+const REDOC_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>vidmeste API</title>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+  </head>
+  <body>
+    <redoc spec-url="/api/docs/openapi.json"></redoc>
+    <script src="/vendor/redoc.standalone.js"></script>
+  </body>
+</html>
+"#;
+
+/// Redoc rendering of the OpenAPI document
+#[get("/")]
+pub async fn docs() -> RawHtml<&'static str> {
+    RawHtml(REDOC_PAGE)
+}
+
+#[get("/openapi.json")]
+pub async fn openapi_json() -> RawJson<String> {
+    RawJson(ApiDoc::openapi().to_pretty_json().unwrap_or_default())
+}
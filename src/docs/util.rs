@@ -0,0 +1,378 @@
+use crate::{
+    caption::model::CaptionTrackInfo,
+    chapter::model::ChapterInfo,
+    clip::model::ClipRequest,
+    collection::model::{CollectionContents, CollectionInfo, NewCollection},
+    comment::model::{CommentInfo, CommentWithAuthor, NewComment},
+    error::ErrorBody,
+    folder::model::{FolderContents, FolderInfo, FolderList, NewFolder},
+    models::{
+        CaptionTrack, Chapter, Collection, Comment, Folder, Job, Playlist, PlaylistItem,
+        StorageHealth, User, Video,
+    },
+    playlist::model::{
+        NewPlaylist, NewPlaylistItem, OneTimePlaylistLink, PlaylistDetails, PlaylistInfo,
+        PlaylistItemDetails, PlaylistOrder,
+    },
+    response::*,
+    search::model::VideoSearchResult,
+    storage::model::ReconcileReport,
+    tag::model::TagCount,
+    video::{
+        model::{VideoDetails, VideoInfo, VideoPage, VideoUnlock, VideoUpload, Visibility},
+        util::VideoAccess,
+    },
+};
+use utoipa::{
+    openapi::{ContentBuilder, OpenApi as OpenApiDocument, Ref, RefOr, ResponseBuilder},
+    Modify, OpenApi,
+};
+
+/// The OpenAPI document of everything mounted under `/api`
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "vidmeste"),
+    paths(
+        crate::api::api::get_all_users,
+        crate::api::api::get_user_by_id,
+        crate::api::api::get_all_videos,
+        crate::api::api::get_video_with_id,
+        crate::api::api::get_public_videos,
+        crate::auth::auth::create_user,
+        crate::auth::auth::me,
+        crate::auth::auth::google_login,
+        crate::auth::auth::google_callback,
+        crate::auth::auth::hogbisz_login,
+        crate::auth::auth::hogbisz_callback,
+        crate::auth::auth::discord_login,
+        crate::auth::auth::discord_callback,
+        crate::auth::auth::logout,
+        crate::event::public::user_events,
+        crate::search::public::search,
+        crate::tag::public::get_tags,
+        crate::video::public::list_videos,
+        crate::video::public::get_video,
+        crate::video::public::add_video,
+        crate::video::public::delete_video,
+        crate::video::public::get_video_info,
+        crate::video::public::edit_video,
+        crate::video::public::unlock_video,
+        crate::comment::public::get_comments,
+        crate::comment::public::add_comment,
+        crate::comment::public::edit_comment,
+        crate::comment::public::resolve_comment,
+        crate::comment::public::delete_comment,
+        crate::caption::public::get_captions,
+        crate::caption::public::upload_captions,
+        crate::caption::public::delete_captions,
+        crate::chapter::public::get_chapters_vtt,
+        crate::chapter::public::set_chapters,
+        crate::clip::public::clip_video,
+        crate::preview::public::get_thumbnail,
+        crate::preview::public::get_sprite_vtt,
+        crate::preview::public::get_preview,
+        crate::audio::public::get_audio,
+        crate::download::public::download_video,
+        crate::event::public::video_events,
+        crate::folder::public::list_folders,
+        crate::folder::public::create_folder,
+        crate::folder::public::get_folder,
+        crate::folder::public::edit_folder,
+        crate::folder::public::delete_folder,
+        crate::collection::public::list_collections,
+        crate::collection::public::create_collection,
+        crate::collection::public::get_collection,
+        crate::collection::public::edit_collection,
+        crate::collection::public::delete_collection,
+        crate::collection::public::add_collection_video,
+        crate::collection::public::remove_collection_video,
+        crate::playlist::public::list_playlists,
+        crate::playlist::public::create_playlist,
+        crate::playlist::public::get_playlist_info,
+        crate::playlist::public::edit_playlist,
+        crate::playlist::public::delete_playlist,
+        crate::playlist::public::add_playlist_item,
+        crate::playlist::public::delete_playlist_item,
+        crate::playlist::public::reorder_playlist,
+        crate::playlist::public::create_one_time_playlist_link,
+        crate::trash::public::list_trash,
+        crate::trash::public::restore_trashed_video,
+        crate::trash::public::purge_trashed_video,
+        crate::storage::public::reconcile,
+        crate::storage::public::storage_health,
+        crate::job::public::list_jobs,
+        crate::job::public::get_job,
+        crate::job::public::retry_job,
+        crate::job::public::cancel_job,
+    ),
+    components(schemas(
+        ErrorBody,
+        ApiMessage,
+        UserResponse,
+        UserListResponse,
+        VideoResponse,
+        VideoListResponse,
+        VideoPageResponse,
+        VideoDetailsResponse,
+        VideoUploadResponse,
+        SearchResponse,
+        TagCountListResponse,
+        CaptionTrackResponse,
+        ChapterListResponse,
+        CommentResponse,
+        CommentListResponse,
+        FolderResponse,
+        FolderListResponse,
+        FolderContentsResponse,
+        CollectionResponse,
+        CollectionListResponse,
+        CollectionContentsResponse,
+        PlaylistResponse,
+        PlaylistListResponse,
+        PlaylistDetailsResponse,
+        PlaylistItemResponse,
+        OneTimePlaylistLinkResponse,
+        ReconcileReportResponse,
+        StorageHealthListResponse,
+        JobResponse,
+        JobListResponse,
+        User,
+        Video,
+        VideoAccess,
+        Visibility,
+        VideoPage,
+        VideoDetails,
+        VideoUpload,
+        VideoSearchResult,
+        TagCount,
+        CaptionTrack,
+        CaptionTrackInfo,
+        Chapter,
+        Comment,
+        CommentWithAuthor,
+        Folder,
+        FolderList,
+        FolderContents,
+        Collection,
+        CollectionContents,
+        Playlist,
+        PlaylistDetails,
+        PlaylistItem,
+        PlaylistItemDetails,
+        OneTimePlaylistLink,
+        VideoInfo,
+        VideoUnlock,
+        NewComment,
+        CommentInfo,
+        ChapterInfo,
+        ClipRequest,
+        NewFolder,
+        FolderInfo,
+        NewCollection,
+        CollectionInfo,
+        NewPlaylist,
+        PlaylistInfo,
+        NewPlaylistItem,
+        PlaylistOrder,
+        ReconcileReport,
+        StorageHealth,
+        Job,
+    )),
+    modifiers(&MountedPaths, &ErrorResponses)
+)]
+pub struct ApiDoc;
+
+/// Rocket serves `#[get("/")]` routes at their mount point without a trailing slash
+struct MountedPaths;
+
+impl Modify for MountedPaths {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let paths = std::mem::take(&mut openapi.paths.paths);
+        for (path, item) in paths {
+            let path = match path.strip_suffix('/') {
+                Some(trimmed) if !trimmed.is_empty() => trimmed.to_string(),
+                _ => path,
+            };
+            openapi.paths.paths.insert(path, item);
+        }
+    }
+}
+
+/// Documents the `AppError` body as the default response of every operation,
+/// so the paths only have to list their successful responses.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.responses.insert(
+                String::from("Error"),
+                RefOr::T(
+                    ResponseBuilder::new()
+                        .description("The request failed, see `error` for why")
+                        .content(
+                            "application/json",
+                            ContentBuilder::new()
+                                .schema(Ref::from_schema_name("ErrorBody"))
+                                .build(),
+                        )
+                        .build(),
+                ),
+            );
+        }
+        for path_item in openapi.paths.paths.values_mut() {
+            for operation in path_item.operations.values_mut() {
+                operation
+                    .responses
+                    .responses
+                    .entry(String::from("default"))
+                    .or_insert_with(|| RefOr::Ref(Ref::from_response_name("Error")));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiDoc;
+    use utoipa::OpenApi;
+
+    /// Turns a Rocket route path like `/api/video/<id>/<file..>` into the
+    /// OpenAPI form `/api/video/{id}/{file}`
+    fn openapi_path(route_path: &str) -> String {
+        route_path
+            .split('/')
+            .map(|segment| match segment.strip_prefix('<') {
+                Some(name) => format!("{{{}}}", name.trim_end_matches('>').trim_end_matches("..")),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    /// The names of a Rocket route's query parameters, `<query..>` for one taking
+    /// all of them as a struct
+    fn query_names(route: &rocket::Route) -> Vec<String> {
+        let mut names = route
+            .uri
+            .query()
+            .map(|query| {
+                query
+                    .split('&')
+                    .map(|segment| segment.trim_start_matches('<').trim_end_matches('>'))
+                    .map(str::to_string)
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// The names of the query parameters an operation documents
+    fn documented_query_names(operation: &serde_json::Value) -> Vec<String> {
+        let mut names = operation["parameters"]
+            .as_array()
+            .map(|parameters| {
+                parameters
+                    .iter()
+                    .filter(|parameter| parameter["in"] == "query")
+                    .filter_map(|parameter| parameter["name"].as_str().map(str::to_string))
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Fails when an API route or schema is added, moved or removed without updating `ApiDoc`.
+    /// Routes that only differ by their query parameters are documented under the
+    /// path with those parameters appended, like `/api/users?id`.
+    #[test]
+    fn openapi_matches_routes() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let rocket = crate::mount_routes(rocket::build());
+        let api_routes = rocket
+            .routes()
+            .filter(|route| {
+                route.uri.path().starts_with("/api") && !route.uri.path().starts_with("/api/docs")
+            })
+            .map(|route| {
+                (
+                    openapi_path(route.uri.path()),
+                    route.method.as_str().to_lowercase(),
+                    query_names(route),
+                )
+            })
+            .collect::<Vec<(String, String, Vec<String>)>>();
+        let routes = api_routes
+            .iter()
+            .map(|(path, method, query)| {
+                let overloaded = api_routes
+                    .iter()
+                    .filter(|(other_path, other_method, _)| {
+                        other_path == path && other_method == method
+                    })
+                    .count()
+                    > 1;
+                let path = if overloaded && !query.is_empty() {
+                    format!("{}?{}", path, query.join("&"))
+                } else {
+                    path.clone()
+                };
+                (path, method.clone(), query.clone())
+            })
+            .collect::<Vec<(String, String, Vec<String>)>>();
+
+        for (path, method, query) in &routes {
+            let operation = &spec["paths"][path][method];
+            assert!(
+                operation.is_object(),
+                "{} {} is not documented",
+                method,
+                path
+            );
+            let documented = documented_query_names(operation);
+            // Query structs are documented as their fields
+            let routed = query
+                .iter()
+                .filter(|name| !name.ends_with(".."))
+                .collect::<Vec<&String>>();
+            let takes_struct = routed.len() < query.len();
+            assert!(
+                routed.iter().all(|name| documented.contains(name))
+                    && (takes_struct || documented.len() == routed.len()),
+                "{} {} takes the query parameters {:?} but documents {:?}",
+                method,
+                path,
+                query,
+                documented
+            );
+        }
+
+        let spec_text = spec.to_string();
+        for reference in spec_text.split("\"$ref\":\"#/components/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            let (kind, name) = name.split_once('/').unwrap();
+            assert!(
+                spec["components"][kind][name].is_object(),
+                "{} {} is referenced but not registered",
+                kind,
+                name
+            );
+        }
+
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                assert!(
+                    routes
+                        .iter()
+                        .any(|(routed_path, routed_method, _)| routed_path == path
+                            && routed_method == method),
+                    "{} {} is documented but not routed",
+                    method,
+                    path
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{
+    auth::util::{get_user_from_cookies, user_has_admin_permission},
+    db::{get_connection, DbPool},
+    download::{sql::insert_video_download, util::attachment_disposition},
+    error::{AppError, AppResult},
+    models::VideoDownloadNoId,
+    video::{
+        sql::get_video_by_video_id,
+        util::{get_filename_ending, get_video_access, with_filename_ending, VideoAccess},
+    },
+};
+use rocket::http::{CookieJar, Header};
+use rocket::State;
+use rocket_seek_stream::SeekStream;
+use std::net::IpAddr;
+
+#[derive(Responder)]
+pub struct VideoDownload<'a> {
+    inner: SeekStream<'a>,
+    content_disposition: Header<'static>,
+}
+
+/// Sends the original video file as an attachment named like the uploaded file
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "download",
+    responses((status = 200, description = "The original video file as an attachment"))
+)]
+#[get("/<id>/download")]
+pub async fn download_video<'a>(
+    id: String,
+    client_ip: Option<IpAddr>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> AppResult<VideoDownload<'a>> {
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if get_video_access(&connection, &video, user.as_ref(), cookies)? != VideoAccess::Allowed {
+        return Err(AppError::NotFound);
+    }
+
+    let can_always_download = user
+        .as_ref()
+        .map(|user| video.owner_id == user.id || user_has_admin_permission(user))
+        .unwrap_or(false);
+    if !video.downloads_enabled && !can_always_download {
+        info!("Downloads are disabled for video {}", video.video_id);
+        return Err(AppError::Forbidden);
+    }
+
+    let stream = SeekStream::from_path(&video.video_path).map_err(AppError::from_file_error)?;
+
+    // The stored file may have been re-encoded into another format since it was uploaded
+    let filename = match get_filename_ending(&video.video_path) {
+        Some(ending) => with_filename_ending(&video.original_filename, &ending),
+        None => video.original_filename.clone(),
+    };
+
+    if let Err(e) = insert_video_download(
+        &connection,
+        &VideoDownloadNoId {
+            video_id: video.id,
+            user_id: user.as_ref().map(|user| user.id),
+            ip_address: client_ip.map(|ip| ip.to_string()),
+        },
+    ) {
+        warn!("Failed to log download of video {} ({})", video.id, e);
+    }
+
+    Ok(VideoDownload {
+        inner: stream,
+        content_disposition: Header::new("Content-Disposition", attachment_disposition(&filename)),
+    })
+}
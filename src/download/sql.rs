@@ -0,0 +1,15 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*};
+use diesel::prelude::*;
+
+/// Logs a download of a video's original file, separately from views
+pub fn insert_video_download(
+    connection: &PgConnection,
+    download: &VideoDownloadNoId,
+) -> AppResult<()> {
+    diesel::insert_into(crate::schema::video_downloads::table)
+        .values(download)
+        .execute(connection)?;
+    Ok(())
+}
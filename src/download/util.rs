@@ -0,0 +1,56 @@
+/// Characters RFC 5987 allows unencoded in an `ext-value`
+fn is_attr_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte)
+}
+
+/// Builds a `Content-Disposition: attachment` value for `filename` (RFC 6266).
+/// Clients that understand `filename*` get the exact UTF-8 name (RFC 5987), older
+/// ones an ASCII approximation.
+pub fn attachment_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let mut encoded = String::with_capacity(filename.len());
+    for byte in filename.bytes() {
+        if is_attr_char(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::attachment_disposition;
+
+    #[test]
+    fn attachment_disposition_keeps_plain_names() {
+        assert_eq!(
+            attachment_disposition("cat.mp4"),
+            "attachment; filename=\"cat.mp4\"; filename*=UTF-8''cat.mp4"
+        );
+    }
+
+    #[test]
+    fn attachment_disposition_escapes_quotes_and_unicode() {
+        assert_eq!(
+            attachment_disposition("kot \"mały\".mp4"),
+            "attachment; filename=\"kot _ma_y_.mp4\"; \
+             filename*=UTF-8''kot%20%22ma%C5%82y%22.mp4"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use rocket::{
+    http::{ContentType, Status},
+    request::Request,
+    response::{self, Responder, Response},
+};
+use serde::Serialize;
+use std::{fmt, io::Cursor};
+use utoipa::ToSchema;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Everything a request can fail with.
+///
+/// Responds with the matching HTTP status and a JSON body in the same shape as
+/// `ApiMessage`, plus a machine-readable `error` code clients can match on.
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest,
+    Unauthorized,
+    /// The video is password protected and has not been unlocked yet
+    PasswordRequired,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PayloadTooLarge,
+    TooManyRequests,
+    /// No database connection became available in time
+    ServiceUnavailable,
+    Db(DieselError),
+    Io(std::io::Error),
+    /// ffprobe or ffmpeg could not read a media file
+    Probe(String),
+    Internal(String),
+}
+
+impl AppError {
+    /// For opening a file that is itself the requested resource, where a missing file
+    /// means the resource does not exist rather than that the server is broken
+    pub fn from_file_error(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound,
+            _ => AppError::Io(e),
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            AppError::BadRequest => Status::BadRequest,
+            AppError::Unauthorized | AppError::PasswordRequired => Status::Unauthorized,
+            AppError::Forbidden => Status::Forbidden,
+            AppError::NotFound => Status::NotFound,
+            AppError::Conflict => Status::Conflict,
+            AppError::PayloadTooLarge => Status::PayloadTooLarge,
+            AppError::TooManyRequests => Status::TooManyRequests,
+            AppError::ServiceUnavailable => Status::ServiceUnavailable,
+            AppError::Db(_) | AppError::Io(_) | AppError::Probe(_) | AppError::Internal(_) => {
+                Status::InternalServerError
+            }
+        }
+    }
+
+    /// The machine-readable code sent as `error`
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest => "bad_request",
+            AppError::Unauthorized => "unauthorized",
+            AppError::PasswordRequired => "password_required",
+            AppError::Forbidden => "forbidden",
+            AppError::NotFound => "not_found",
+            AppError::Conflict => "conflict",
+            AppError::PayloadTooLarge => "payload_too_large",
+            AppError::TooManyRequests => "too_many_requests",
+            AppError::ServiceUnavailable => "service_unavailable",
+            AppError::Db(_) => "database_error",
+            AppError::Io(_) => "io_error",
+            AppError::Probe(_) => "probe_failed",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// The human readable `message`. Details of internal errors are only logged.
+    pub fn message(&self) -> &'static str {
+        match self {
+            AppError::BadRequest => "Bad Request",
+            AppError::Unauthorized => "Unauthorized",
+            AppError::PasswordRequired => "Password Required",
+            AppError::Forbidden => "Forbidden",
+            AppError::NotFound => "Not found",
+            AppError::Conflict => "Conflict",
+            AppError::PayloadTooLarge => "Payload Too Large",
+            AppError::TooManyRequests => "Too Many Requests",
+            AppError::ServiceUnavailable => "Service Unavailable",
+            AppError::Db(_) | AppError::Io(_) | AppError::Probe(_) | AppError::Internal(_) => {
+                "Internal Server Error"
+            }
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Db(e) => write!(f, "database error: {}", e),
+            AppError::Io(e) => write!(f, "io error: {}", e),
+            AppError::Probe(e) => write!(f, "probe failed: {}", e),
+            AppError::Internal(e) => write!(f, "internal error: {}", e),
+            _ => f.write_str(self.code()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// The body of every error response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub status: u16,
+    /// Machine-readable, e.g. `not_found` or `password_required`
+    pub error: &'static str,
+    pub message: &'static str,
+}
+
+impl From<DieselError> for AppError {
+    fn from(e: DieselError) -> Self {
+        match e {
+            DieselError::NotFound => AppError::NotFound,
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => AppError::Conflict,
+            e => AppError::Db(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for AppError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status();
+        if status == Status::InternalServerError {
+            warn!(
+                "{} {} failed with {}",
+                request.method(),
+                request.uri(),
+                self
+            );
+        }
+        let body = serde_json::to_string(&ErrorBody {
+            status: status.code,
+            error: self.code(),
+            message: self.message(),
+        })
+        .unwrap_or_default();
+        Response::build()
+            .status(status)
+            .header(ContentType::JSON)
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
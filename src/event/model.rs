@@ -0,0 +1,45 @@
+use serde::Serialize;
+
+/// Progress of an upload or clip, pushed to the event streams of the video's owner
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoEvent {
+    pub video_id: String,
+    #[serde(skip)]
+    pub owner_id: i32,
+    #[serde(flatten)]
+    pub kind: VideoEventKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum VideoEventKind {
+    /// The uploaded file is stored and waiting to be probed
+    UploadReceived,
+    ProbeDone {
+        video_length: f64,
+    },
+    /// How far ffmpeg is through writing a clip
+    Transcoding {
+        percent: u8,
+    },
+    Ready,
+    /// The thumbnail, seek bar sprite sheet and previews were generated
+    PreviewsReady,
+    Failed {
+        reason: String,
+    },
+}
+
+impl VideoEventKind {
+    /// The SSE event name, matching the `event` field of the data
+    pub fn name(&self) -> &'static str {
+        match self {
+            VideoEventKind::UploadReceived => "upload_received",
+            VideoEventKind::ProbeDone { .. } => "probe_done",
+            VideoEventKind::Transcoding { .. } => "transcoding",
+            VideoEventKind::Ready => "ready",
+            VideoEventKind::PreviewsReady => "previews_ready",
+            VideoEventKind::Failed { .. } => "failed",
+        }
+    }
+}
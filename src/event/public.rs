@@ -0,0 +1,59 @@
+use crate::{
+    auth::util::get_user_from_cookies,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    event::util::VideoEvents,
+    video::sql::get_video_by_video_id,
+};
+use rocket::{http::CookieJar, response::stream::EventStream, Shutdown, State};
+
+/// Streams the processing progress of one of the user's videos
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    responses((status = 200, description = "Server-sent events named upload_received, probe_done, transcoding, ready, previews_ready and failed, with the video_id and event in their JSON data", body = String, content_type = "text/event-stream"))
+)]
+#[get("/<id>/events")]
+pub async fn video_events(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+    events: &State<VideoEvents>,
+    shutdown: Shutdown,
+) -> AppResult<EventStream![]> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_video_by_video_id(&connection, &id)?;
+
+    if video.owner_id != user.id {
+        info!("User {} is not the owner of video {}", user.id, video.id);
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(events.stream(move |event| event.video_id == video.video_id, shutdown))
+}
+
+/// Streams the processing progress of all of the user's videos
+#[utoipa::path(
+    context_path = "/api",
+    tag = "video",
+    responses((status = 200, description = "Server-sent events named upload_received, probe_done, transcoding, ready, previews_ready and failed, with the video_id and event in their JSON data", body = String, content_type = "text/event-stream"))
+)]
+#[get("/events")]
+pub async fn user_events(
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+    events: &State<VideoEvents>,
+    shutdown: Shutdown,
+) -> AppResult<EventStream![]> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    Ok(events.stream(move |event| event.owner_id == user.id, shutdown))
+}
@@ -0,0 +1,62 @@
+use crate::{
+    event::model::{VideoEvent, VideoEventKind},
+    models::Video,
+};
+use rocket::{
+    response::stream::{Event, EventStream},
+    tokio::{
+        select,
+        sync::broadcast::{self, error::RecvError},
+    },
+    Shutdown,
+};
+
+/// Events a stream may fall behind by before it skips ahead
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Broadcasts the progress of jobs to every open event stream
+#[derive(Clone)]
+pub struct VideoEvents(broadcast::Sender<VideoEvent>);
+
+impl Default for VideoEvents {
+    fn default() -> Self {
+        VideoEvents(broadcast::channel(EVENT_CHANNEL_CAPACITY).0)
+    }
+}
+
+impl VideoEvents {
+    pub fn publish(&self, video: &Video, kind: VideoEventKind) {
+        // Sending only fails when no stream is open, which is fine
+        let _ = self.0.send(VideoEvent {
+            video_id: video.video_id.clone(),
+            owner_id: video.owner_id,
+            kind,
+        });
+    }
+
+    /// Streams the events `filter` accepts until the client disconnects or the server stops
+    pub fn stream<F>(&self, filter: F, mut shutdown: Shutdown) -> EventStream![]
+    where
+        F: Fn(&VideoEvent) -> bool + Send + 'static,
+    {
+        let mut receiver = self.0.subscribe();
+        EventStream! {
+            loop {
+                let event = select! {
+                    event = receiver.recv() => match event {
+                        Ok(event) => event,
+                        Err(RecvError::Closed) => break,
+                        Err(RecvError::Lagged(skipped)) => {
+                            info!("Event stream fell behind, skipped {} events", skipped);
+                            continue;
+                        }
+                    },
+                    _ = &mut shutdown => break,
+                };
+                if filter(&event) {
+                    yield Event::json(&event).event(event.kind.name());
+                }
+            }
+        }
+    }
+}
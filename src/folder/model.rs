@@ -0,0 +1,32 @@
+use crate::models::{Folder, Video};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewFolder {
+    pub name: String,
+    /// The `folder_id` of the folder to create this one in, None for the top level
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FolderInfo {
+    pub name: Option<String>,
+    /// Moves the folder into the folder with this `folder_id`. An empty string moves it to the top level.
+    pub parent: Option<String>,
+    pub share: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FolderContents {
+    pub folder: Folder,
+    pub folders: Vec<Folder>,
+    pub videos: Vec<Video>,
+}
+
+/// The caller's top level folders and the folders shared with them
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FolderList {
+    pub folders: Vec<Folder>,
+    pub shared: Vec<Folder>,
+}
@@ -0,0 +1,259 @@
+use crate::{
+    auth::{sql::get_user_by_user_id, util::get_user_from_cookies},
+    db::{get_connection, DbPool},
+    error::AppError,
+    folder::{
+        model::{FolderContents, FolderInfo, FolderList, NewFolder},
+        sql::{
+            delete_folder_with_id, generate_new_folder_id, get_child_folders,
+            get_folder_by_folder_id, get_folders_shared_with_user, insert_folder,
+            insert_folder_share, update_folder,
+        },
+        util::{folder_is_inside, user_can_view_folder},
+    },
+    models::{Folder, FolderNoId},
+    response::{ApiMessage, ApiMessageResult, ApiResponse, ApiResult},
+    video::{sql::get_videos_in_folder, util::user_can_view_video},
+};
+use diesel::Connection;
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use rocket::State;
+
+const MAX_FOLDER_NAME_LENGTH: usize = 128;
+
+#[utoipa::path(
+    context_path = "/api/folder",
+    tag = "folder",
+    responses((status = 200, body = FolderListResponse))
+)]
+#[get("/")]
+pub async fn list_folders(cookies: &CookieJar<'_>, pool: &State<DbPool>) -> ApiResult<FolderList> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let folders = get_child_folders(&connection, user.id, None)?;
+    let shared = get_folders_shared_with_user(&connection, user.id)?;
+
+    Ok(ApiResponse::ok(FolderList { folders, shared }))
+}
+
+#[utoipa::path(
+    context_path = "/api/folder",
+    tag = "folder",
+    request_body = NewFolder,
+    responses((status = 200, body = FolderResponse))
+)]
+#[post("/", data = "<folder>", format = "json")]
+pub async fn create_folder(
+    folder: Json<NewFolder>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Folder> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut name = folder.name.trim().to_string();
+    if name.is_empty() {
+        info!("Folder name is empty");
+        return Err(AppError::BadRequest);
+    }
+    if name.len() > MAX_FOLDER_NAME_LENGTH {
+        info!("Name too long. Cutting off at 128 characters");
+        name.truncate(MAX_FOLDER_NAME_LENGTH);
+    }
+
+    let parent_id = match &folder.parent {
+        Some(parent) => match get_folder_by_folder_id(&connection, parent) {
+            Ok(parent) if parent.owner_id == user.id => Some(parent.id),
+            Ok(_) => {
+                info!("User {} does not own folder {}", user.id, parent);
+                return Err(AppError::Forbidden);
+            }
+            Err(AppError::NotFound) => return Err(AppError::BadRequest),
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+
+    let folder = insert_folder(
+        &connection,
+        &FolderNoId {
+            folder_id: generate_new_folder_id(&connection)?,
+            owner_id: user.id,
+            parent_id,
+            name,
+        },
+    )?;
+
+    Ok(ApiResponse::ok(folder))
+}
+
+#[utoipa::path(
+    context_path = "/api/folder",
+    tag = "folder",
+    responses((status = 200, body = FolderContentsResponse))
+)]
+#[get("/<id>")]
+pub async fn get_folder(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<FolderContents> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let folder = get_folder_by_folder_id(&connection, &id)?;
+
+    if !user_can_view_folder(&connection, &folder, &user)? {
+        info!("User {} can not view folder {}", user.id, folder.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let folders = get_child_folders(&connection, folder.owner_id, Some(folder.id))?;
+    let mut videos = Vec::new();
+    for video in get_videos_in_folder(&connection, folder.id)? {
+        if user_can_view_video(&connection, &video, Some(&user))? {
+            videos.push(video);
+        }
+    }
+
+    Ok(ApiResponse::ok(FolderContents {
+        folder,
+        folders,
+        videos,
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/api/folder",
+    tag = "folder",
+    request_body = FolderInfo,
+    responses((status = 200, body = FolderResponse))
+)]
+#[post("/edit?<id>", data = "<info>", format = "json")]
+pub async fn edit_folder(
+    id: String,
+    info: Json<FolderInfo>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Folder> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut folder = get_folder_by_folder_id(&connection, &id)?;
+
+    if folder.owner_id != user.id {
+        info!("User {} is not the owner of folder {}", user.id, folder.id);
+        return Err(AppError::Forbidden);
+    }
+
+    if let Some(name) = &info.name {
+        let mut name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::BadRequest);
+        }
+        if name.len() > MAX_FOLDER_NAME_LENGTH {
+            info!("Name too long. Cutting off at 128 characters");
+            name.truncate(MAX_FOLDER_NAME_LENGTH);
+        }
+        folder.name = name;
+    }
+
+    if let Some(parent) = &info.parent {
+        if parent.is_empty() {
+            folder.parent_id = None;
+        } else {
+            let parent = match get_folder_by_folder_id(&connection, parent) {
+                Ok(parent) => parent,
+                Err(AppError::NotFound) => return Err(AppError::BadRequest),
+                Err(e) => return Err(e),
+            };
+            if parent.owner_id != user.id {
+                info!("User {} does not own folder {}", user.id, parent.id);
+                return Err(AppError::Forbidden);
+            }
+            if folder_is_inside(&connection, &parent, &folder)? {
+                info!(
+                    "Can not move folder {} into itself or one of its subfolders",
+                    folder.id
+                );
+                return Err(AppError::BadRequest);
+            }
+            folder.parent_id = Some(parent.id);
+        }
+    }
+
+    // Shares are only changed along with the folder
+    let folder = connection.transaction::<_, AppError, _>(|| {
+        if let Some(shared_ids) = &info.share {
+            for id in shared_ids {
+                let user_share = match get_user_by_user_id(&connection, id) {
+                    Ok(u) => u,
+                    Err(AppError::NotFound) => {
+                        info!("No user found with user_id {}", id);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+                if user_share.id == user.id {
+                    info!(
+                        "User {} is trying to share folder {} with themselves",
+                        user.id, folder.id
+                    );
+                    continue;
+                }
+                insert_folder_share(&connection, folder.id, user_share.id)?;
+            }
+        }
+        update_folder(&connection, &folder)
+    })?;
+
+    Ok(ApiResponse::ok(folder))
+}
+
+#[utoipa::path(
+    context_path = "/api/folder",
+    tag = "folder",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>")]
+pub async fn delete_folder(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let folder = get_folder_by_folder_id(&connection, &id)?;
+
+    if folder.owner_id != user.id && !crate::auth::util::user_has_admin_permission(&user) {
+        info!("User did not own folder that was attempted to be deleted.");
+        return Err(AppError::Forbidden);
+    }
+
+    delete_folder_with_id(&connection, folder.id)?;
+
+    Ok(ApiMessage::ok())
+}
@@ -0,0 +1,118 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*, util::make_random_string};
+use diesel::{
+    prelude::*,
+    sql_types::{Bool, Integer},
+};
+
+// Defined in the folders_collections migration
+sql_function!(fn folder_shared_with(folder: Integer, viewer: Integer) -> Bool);
+
+pub fn get_folder_by_id(connection: &PgConnection, id: i32) -> AppResult<Folder> {
+    Ok(crate::schema::folders::table
+        .filter(crate::schema::folders::dsl::id.eq(id))
+        .first::<Folder>(connection)?)
+}
+
+pub fn get_folder_by_folder_id(connection: &PgConnection, id: &String) -> AppResult<Folder> {
+    Ok(crate::schema::folders::table
+        .filter(crate::schema::folders::dsl::folder_id.eq(id.to_owned()))
+        .first::<Folder>(connection)?)
+}
+
+fn get_folder_by_folder_id_no_error(
+    connection: &PgConnection,
+    id: &String,
+) -> AppResult<Option<Folder>> {
+    Ok(crate::schema::folders::table
+        .filter(crate::schema::folders::dsl::folder_id.eq(id.to_owned()))
+        .first::<Folder>(connection)
+        .optional()?)
+}
+
+/// Gets the folders directly inside `parent_id`, or a user's top level folders if None
+pub fn get_child_folders(
+    connection: &PgConnection,
+    owner_id: i32,
+    parent_id: Option<i32>,
+) -> AppResult<Vec<Folder>> {
+    use crate::schema::folders::dsl;
+
+    let query = match parent_id {
+        Some(parent_id) => crate::schema::folders::table
+            .filter(dsl::parent_id.eq(parent_id))
+            .into_boxed(),
+        None => crate::schema::folders::table
+            .filter(dsl::owner_id.eq(owner_id))
+            .filter(dsl::parent_id.is_null())
+            .into_boxed(),
+    };
+    Ok(query.order(dsl::name.asc()).load::<Folder>(connection)?)
+}
+
+/// Gets the folders that were shared directly with a user
+pub fn get_folders_shared_with_user(
+    connection: &PgConnection,
+    user_id: i32,
+) -> AppResult<Vec<Folder>> {
+    use crate::schema::{folder_shares, folders};
+
+    Ok(folders::table
+        .inner_join(folder_shares::table)
+        .filter(folder_shares::dsl::user_id.eq(user_id))
+        .select(folders::all_columns)
+        .order(folders::dsl::name.asc())
+        .load::<Folder>(connection)?)
+}
+
+pub fn insert_folder(connection: &PgConnection, folder: &FolderNoId) -> AppResult<Folder> {
+    Ok(diesel::insert_into(crate::schema::folders::table)
+        .values(folder)
+        .get_result::<Folder>(connection)?)
+}
+
+pub fn update_folder(connection: &PgConnection, folder: &Folder) -> AppResult<Folder> {
+    Ok(diesel::update(
+        crate::schema::folders::table.filter(crate::schema::folders::dsl::id.eq(folder.id)),
+    )
+    .set(folder)
+    .get_result::<Folder>(connection)?)
+}
+
+/// Deletes a folder and its subfolders. Videos inside are moved to the top level.
+pub fn delete_folder_with_id(connection: &PgConnection, id: i32) -> AppResult<()> {
+    diesel::delete(crate::schema::folders::table.filter(crate::schema::folders::dsl::id.eq(id)))
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn insert_folder_share(
+    connection: &PgConnection,
+    folder_id: i32,
+    user_id: i32,
+) -> AppResult<()> {
+    diesel::insert_into(crate::schema::folder_shares::table)
+        .values(&FolderShareNoId { folder_id, user_id })
+        .on_conflict_do_nothing()
+        .execute(connection)?;
+    Ok(())
+}
+
+/// Whether a folder, or any folder above it, is shared with a user
+pub fn folder_is_shared_with_user(
+    connection: &PgConnection,
+    folder_id: i32,
+    user_id: i32,
+) -> AppResult<bool> {
+    Ok(diesel::select(folder_shared_with(folder_id, user_id)).get_result::<bool>(connection)?)
+}
+
+/// Generates a new folder id that does not exist in the database
+pub fn generate_new_folder_id(connection: &PgConnection) -> AppResult<String> {
+    let mut folder_id = make_random_string(32);
+    while get_folder_by_folder_id_no_error(connection, &folder_id)?.is_some() {
+        folder_id = make_random_string(32);
+    }
+    Ok(folder_id)
+}
@@ -0,0 +1,37 @@
+use crate::{
+    auth::util::user_has_admin_permission,
+    error::AppResult,
+    folder::sql::{folder_is_shared_with_user, get_folder_by_id},
+    models::{Folder, User},
+};
+use diesel::PgConnection;
+
+/// Owners, admins and users the folder (or a folder above it) is shared with can view a folder
+pub fn user_can_view_folder(
+    connection: &PgConnection,
+    folder: &Folder,
+    user: &User,
+) -> AppResult<bool> {
+    Ok(folder.owner_id == user.id
+        || user_has_admin_permission(user)
+        || folder_is_shared_with_user(connection, folder.id, user.id)?)
+}
+
+/// Whether `folder` is `ancestor` or somewhere inside it
+pub fn folder_is_inside(
+    connection: &PgConnection,
+    folder: &Folder,
+    ancestor: &Folder,
+) -> AppResult<bool> {
+    if folder.id == ancestor.id {
+        return Ok(true);
+    }
+    let mut parent_id = folder.parent_id;
+    while let Some(id) = parent_id {
+        if id == ancestor.id {
+            return Ok(true);
+        }
+        parent_id = get_folder_by_id(connection, id)?.parent_id;
+    }
+    Ok(false)
+}
@@ -0,0 +1,4 @@
+pub mod model;
+pub mod public;
+pub mod sql;
+pub mod util;
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Everything that can run as a job, stored as JSON in `jobs.payload`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobKind {
+    /// Reads the length of an upload, then queues the rest of its processing
+    ProbeVideo {
+        video_id: i32,
+    },
+    /// Imports the captions and chapters embedded in a video
+    ImportMetadata {
+        video_id: i32,
+    },
+    GeneratePreviews {
+        video_id: i32,
+    },
+    #[cfg(feature = "transcription")]
+    Transcribe {
+        video_id: i32,
+    },
+    /// See `crate::clip::util::run_clip_job`
+    ClipVideo {
+        source_id: i32,
+        target_id: i32,
+        start: f64,
+        end: f64,
+    },
+    /// Configuration is read when the job is scheduled and kept in the payload
+    PurgeTrash {
+        retention_days: i32,
+    },
+    ReconcileStorage {
+        repair: bool,
+    },
+    ScrubStorage {
+        max_bytes_per_second: u64,
+    },
+}
+
+impl JobKind {
+    /// The name stored in `jobs.kind`, matching the `kind` tag of the payload
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobKind::ProbeVideo { .. } => "probe_video",
+            JobKind::ImportMetadata { .. } => "import_metadata",
+            JobKind::GeneratePreviews { .. } => "generate_previews",
+            #[cfg(feature = "transcription")]
+            JobKind::Transcribe { .. } => "transcribe",
+            JobKind::ClipVideo { .. } => "clip_video",
+            JobKind::PurgeTrash { .. } => "purge_trash",
+            JobKind::ReconcileStorage { .. } => "reconcile_storage",
+            JobKind::ScrubStorage { .. } => "scrub_storage",
+        }
+    }
+
+    pub fn queue(&self) -> JobQueue {
+        match self {
+            JobKind::PurgeTrash { .. }
+            | JobKind::ReconcileStorage { .. }
+            | JobKind::ScrubStorage { .. } => JobQueue::Maintenance,
+            _ => JobQueue::Media,
+        }
+    }
+}
+
+/// Each queue has its own workers, so long maintenance jobs never hold up uploads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobQueue {
+    /// Processing of uploads and clips, mostly ffmpeg
+    Media,
+    /// Purges and storage checks, which read a lot from disk
+    Maintenance,
+}
+
+impl JobQueue {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobQueue::Media => "media",
+            JobQueue::Maintenance => "maintenance",
+        }
+    }
+}
+
+/// Stored as text in `jobs.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting for a worker, possibly to be retried after a failed attempt
+    Queued,
+    Running,
+    Succeeded,
+    /// Failed on every attempt, only run again when retried by an admin
+    Dead,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Dead => "dead",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "succeeded" => Ok(JobStatus::Succeeded),
+            "dead" => Ok(JobStatus::Dead),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            _ => Err(()),
+        }
+    }
+}
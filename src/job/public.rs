@@ -0,0 +1,164 @@
+use crate::{
+    auth::util::{get_user_from_cookies, user_has_admin_permission},
+    db::{get_connection, DbPool},
+    error::AppError,
+    event::util::VideoEvents,
+    job::{
+        model::{JobKind, JobStatus},
+        sql::{get_job_by_id, get_jobs, requeue_job},
+        util::{fail_cancelled_job, RunningJobs},
+    },
+    models::Job,
+    response::{ApiResponse, ApiResult},
+};
+use rocket::http::CookieJar;
+use rocket::State;
+
+const DEFAULT_JOB_LIMIT: i64 = 100;
+const MAX_JOB_LIMIT: i64 = 500;
+
+/// Lists the most recent jobs, optionally only those with a `status` or `kind`
+#[utoipa::path(
+    context_path = "/api/jobs",
+    tag = "admin",
+    responses((status = 200, body = JobListResponse))
+)]
+#[get("/?<status>&<kind>&<limit>")]
+pub async fn list_jobs(
+    status: Option<String>,
+    kind: Option<String>,
+    limit: Option<i64>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<Job>> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    if !user_has_admin_permission(&user) {
+        info!("User {} can not list jobs", user.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let status = match status {
+        Some(status) => match status.parse::<JobStatus>() {
+            Ok(status) => Some(status),
+            Err(_) => {
+                info!("Invalid job status {}", status);
+                return Err(AppError::BadRequest);
+            }
+        },
+        None => None,
+    };
+    let limit = limit.unwrap_or(DEFAULT_JOB_LIMIT).clamp(1, MAX_JOB_LIMIT);
+
+    let jobs = get_jobs(&connection, status, kind.as_deref(), limit)?;
+
+    Ok(ApiResponse::ok(jobs))
+}
+
+#[utoipa::path(
+    context_path = "/api/jobs",
+    tag = "admin",
+    responses((status = 200, body = JobResponse))
+)]
+#[get("/<id>")]
+pub async fn get_job(id: i32, cookies: &CookieJar<'_>, pool: &State<DbPool>) -> ApiResult<Job> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    if !user_has_admin_permission(&user) {
+        info!("User {} can not see job {}", user.id, id);
+        return Err(AppError::Forbidden);
+    }
+
+    let job = get_job_by_id(&connection, id)?;
+
+    Ok(ApiResponse::ok(job))
+}
+
+/// Queues a dead or cancelled job again with all its attempts
+#[utoipa::path(
+    context_path = "/api/jobs",
+    tag = "admin",
+    responses((status = 200, body = JobResponse))
+)]
+#[post("/<id>/retry")]
+pub async fn retry_job(id: i32, cookies: &CookieJar<'_>, pool: &State<DbPool>) -> ApiResult<Job> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    if !user_has_admin_permission(&user) {
+        info!("User {} can not retry job {}", user.id, id);
+        return Err(AppError::Forbidden);
+    }
+
+    let job = get_job_by_id(&connection, id)?;
+
+    match requeue_job(&connection, job.id)? {
+        Some(job) => Ok(ApiResponse::ok(job)),
+        None => {
+            info!("Job {} is {}, not dead or cancelled", job.id, job.status);
+            Err(AppError::Conflict)
+        }
+    }
+}
+
+/// Cancels a queued job, or stops a running one. The video an upload or clip job
+/// was processing is marked as failed.
+#[utoipa::path(
+    context_path = "/api/jobs",
+    tag = "admin",
+    responses((status = 200, body = JobResponse))
+)]
+#[post("/<id>/cancel")]
+pub async fn cancel_job(
+    id: i32,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+    running: &State<RunningJobs>,
+    events: &State<VideoEvents>,
+) -> ApiResult<Job> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    if !user_has_admin_permission(&user) {
+        info!("User {} can not cancel job {}", user.id, id);
+        return Err(AppError::Forbidden);
+    }
+
+    let job = get_job_by_id(&connection, id)?;
+
+    // Marked as cancelled before stopping it, so it can't record an outcome in between
+    let cancelled = match crate::job::sql::cancel_job(&connection, job.id)? {
+        Some(cancelled) => cancelled,
+        None => {
+            info!("Job {} already ended as {}", job.id, job.status);
+            return Err(AppError::Conflict);
+        }
+    };
+    if running.abort(job.id) {
+        info!("Stopped running job {}", job.id);
+    }
+    match serde_json::from_str::<JobKind>(&cancelled.payload) {
+        Ok(kind) => fail_cancelled_job(&connection, events, &kind)?,
+        Err(e) => warn!("Job {} has an invalid payload ({})", job.id, e),
+    }
+
+    Ok(ApiResponse::ok(cancelled))
+}
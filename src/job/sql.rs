@@ -0,0 +1,181 @@
+extern crate diesel;
+
+use crate::{error::AppResult, job::model::JobStatus, models::*};
+use diesel::{
+    dsl::{exists, now, IntervalDsl},
+    prelude::*,
+    sql_types::{BigInt, Text},
+};
+
+pub fn insert_job(connection: &PgConnection, job: &JobNoId) -> AppResult<Job> {
+    Ok(diesel::insert_into(crate::schema::jobs::table)
+        .values(job)
+        .get_result::<Job>(connection)?)
+}
+
+pub fn get_job_by_id(connection: &PgConnection, id: i32) -> AppResult<Job> {
+    Ok(crate::schema::jobs::table
+        .filter(crate::schema::jobs::dsl::id.eq(id))
+        .first::<Job>(connection)?)
+}
+
+/// Gets the most recent jobs, optionally only those with a status or kind
+pub fn get_jobs(
+    connection: &PgConnection,
+    status: Option<JobStatus>,
+    kind: Option<&str>,
+    limit: i64,
+) -> AppResult<Vec<Job>> {
+    use crate::schema::jobs::dsl;
+
+    let mut query = crate::schema::jobs::table.into_boxed();
+    if let Some(status) = status {
+        query = query.filter(dsl::status.eq(status.as_str()));
+    }
+    if let Some(kind) = kind {
+        query = query.filter(dsl::kind.eq(kind.to_owned()));
+    }
+    Ok(query
+        .order(dsl::id.desc())
+        .limit(limit)
+        .load::<Job>(connection)?)
+}
+
+/// Whether a job of `kind` is waiting or running
+pub fn job_is_pending(connection: &PgConnection, kind: &str) -> AppResult<bool> {
+    use crate::schema::jobs::dsl;
+
+    Ok(diesel::select(exists(
+        crate::schema::jobs::table
+            .filter(dsl::kind.eq(kind))
+            .filter(dsl::status.eq_any(vec![
+                JobStatus::Queued.as_str(),
+                JobStatus::Running.as_str(),
+            ])),
+    ))
+    .get_result::<bool>(connection)?)
+}
+
+/// Marks up to `limit` due jobs of a queue as running and returns them.
+/// Jobs another connection is claiming at the same time are skipped.
+pub fn claim_jobs(connection: &PgConnection, queue: &str, limit: i64) -> AppResult<Vec<Job>> {
+    Ok(diesel::sql_query(
+        "UPDATE jobs
+        SET status = 'running', attempts = attempts + 1, updated_at = CURRENT_TIMESTAMP
+        WHERE id IN (
+            SELECT id FROM jobs
+            WHERE queue = $1 AND status = 'queued' AND run_at <= CURRENT_TIMESTAMP
+            ORDER BY run_at ASC, id ASC
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *",
+    )
+    .bind::<Text, _>(queue)
+    .bind::<BigInt, _>(limit)
+    .load::<Job>(connection)?)
+}
+
+/// Puts jobs that were running when the server stopped back in their queue
+pub fn requeue_interrupted_jobs(connection: &PgConnection) -> AppResult<usize> {
+    use crate::schema::jobs::dsl;
+
+    Ok(diesel::update(
+        crate::schema::jobs::table.filter(dsl::status.eq(JobStatus::Running.as_str())),
+    )
+    .set((
+        dsl::status.eq(JobStatus::Queued.as_str()),
+        dsl::updated_at.eq(now),
+    ))
+    .execute(connection)?)
+}
+
+/// Records the outcome of a running job. Does nothing if the job was cancelled meanwhile.
+pub fn finish_job(
+    connection: &PgConnection,
+    id: i32,
+    status: JobStatus,
+    error: Option<&str>,
+) -> AppResult<()> {
+    use crate::schema::jobs::dsl;
+
+    diesel::update(
+        crate::schema::jobs::table
+            .filter(dsl::id.eq(id))
+            .filter(dsl::status.eq(JobStatus::Running.as_str())),
+    )
+    .set((
+        dsl::status.eq(status.as_str()),
+        dsl::last_error.eq(error),
+        dsl::updated_at.eq(now),
+    ))
+    .execute(connection)?;
+    Ok(())
+}
+
+/// Queues a running job that failed again, to run in `delay_seconds`
+pub fn retry_job_later(
+    connection: &PgConnection,
+    id: i32,
+    delay_seconds: i32,
+    error: &str,
+) -> AppResult<()> {
+    use crate::schema::jobs::dsl;
+
+    diesel::update(
+        crate::schema::jobs::table
+            .filter(dsl::id.eq(id))
+            .filter(dsl::status.eq(JobStatus::Running.as_str())),
+    )
+    .set((
+        dsl::status.eq(JobStatus::Queued.as_str()),
+        dsl::run_at.eq(now + delay_seconds.seconds()),
+        dsl::last_error.eq(error),
+        dsl::updated_at.eq(now),
+    ))
+    .execute(connection)?;
+    Ok(())
+}
+
+/// Cancels a queued or running job, returning None if it already ended
+pub fn cancel_job(connection: &PgConnection, id: i32) -> AppResult<Option<Job>> {
+    use crate::schema::jobs::dsl;
+
+    Ok(
+        diesel::update(crate::schema::jobs::table.filter(dsl::id.eq(id)).filter(
+            dsl::status.eq_any(vec![
+                JobStatus::Queued.as_str(),
+                JobStatus::Running.as_str(),
+            ]),
+        ))
+        .set((
+            dsl::status.eq(JobStatus::Cancelled.as_str()),
+            dsl::updated_at.eq(now),
+        ))
+        .get_result::<Job>(connection)
+        .optional()?,
+    )
+}
+
+/// Queues a dead or cancelled job to run right away with all its attempts,
+/// returning None if it has not ended that way
+pub fn requeue_job(connection: &PgConnection, id: i32) -> AppResult<Option<Job>> {
+    use crate::schema::jobs::dsl;
+
+    Ok(
+        diesel::update(crate::schema::jobs::table.filter(dsl::id.eq(id)).filter(
+            dsl::status.eq_any(vec![
+                JobStatus::Dead.as_str(),
+                JobStatus::Cancelled.as_str(),
+            ]),
+        ))
+        .set((
+            dsl::status.eq(JobStatus::Queued.as_str()),
+            dsl::attempts.eq(0),
+            dsl::run_at.eq(now),
+            dsl::updated_at.eq(now),
+        ))
+        .get_result::<Job>(connection)
+        .optional()?,
+    )
+}
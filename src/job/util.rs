@@ -0,0 +1,348 @@
+use crate::{
+    caption::util::import_embedded_captions,
+    chapter::util::import_chapters,
+    clip::util::{fail_clip, run_clip_job},
+    db::{env_or, get_connection, DbPool},
+    error::{AppError, AppResult},
+    event::{model::VideoEventKind, util::VideoEvents},
+    job::{
+        model::{JobKind, JobQueue, JobStatus},
+        sql::{claim_jobs, finish_job, insert_job, job_is_pending, retry_job_later},
+    },
+    models::{Job, JobNoId, Video},
+    preview::util::generate_previews,
+    storage::util::{reconcile_storage_in_background, scrub_storage},
+    trash::util::purge_expired_videos,
+    video::{
+        sql::get_video_by_id,
+        util::{fail_upload, probe_upload},
+    },
+};
+use diesel::PgConnection;
+use rocket::tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinHandle,
+    time::interval,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+const POLL_INTERVAL_MILLISECONDS: u64 = 1000;
+const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+/// Failed jobs are retried after this, doubling with every attempt up to `MAX_RETRY_DELAY_SECONDS`
+const RETRY_BASE_DELAY_SECONDS: i32 = 30;
+const MAX_RETRY_DELAY_SECONDS: i32 = 60 * 60;
+const DEFAULT_MEDIA_CONCURRENCY: usize = 2;
+const DEFAULT_MAINTENANCE_CONCURRENCY: usize = 1;
+
+/// The jobs running in this process, so they can be cancelled
+#[derive(Clone, Default)]
+pub struct RunningJobs(Arc<Mutex<HashMap<i32, JoinHandle<()>>>>);
+
+impl RunningJobs {
+    fn handles(&self) -> std::sync::MutexGuard<'_, HashMap<i32, JoinHandle<()>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stops a job if it is running, returning whether it was
+    pub fn abort(&self, id: i32) -> bool {
+        match self.handles().remove(&id) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Queues a job for the workers of its queue
+pub fn enqueue_job(connection: &PgConnection, job: &JobKind) -> AppResult<Job> {
+    let payload = match serde_json::to_string(job) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return Err(AppError::Internal(format!(
+                "failed to serialize {} job ({})",
+                job.name(),
+                e
+            )))
+        }
+    };
+    insert_job(
+        connection,
+        &JobNoId {
+            queue: job.queue().as_str().to_string(),
+            kind: job.name().to_string(),
+            payload,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        },
+    )
+}
+
+/// Queues `job` every `period`, starting at launch, unless one of its kind is still
+/// waiting or running
+pub async fn schedule_job(pool: DbPool, job: JobKind, period: Duration) {
+    let mut interval = interval(period);
+    loop {
+        interval.tick().await;
+        let connection = match get_connection(&pool).await {
+            Some(connection) => connection,
+            None => continue,
+        };
+        let queued = match job_is_pending(&connection, job.name()) {
+            Ok(true) => continue,
+            Ok(false) => enqueue_job(&connection, &job),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = queued {
+            warn!("Failed to schedule {} job ({})", job.name(), e);
+        }
+    }
+}
+
+fn retry_delay_seconds(attempts: i32) -> i32 {
+    let doublings = (attempts - 1).clamp(0, 16) as u32;
+    RETRY_BASE_DELAY_SECONDS
+        .saturating_mul(2i32.pow(doublings))
+        .min(MAX_RETRY_DELAY_SECONDS)
+}
+
+/// Loads the video a job is for, None if it was deleted since the job was queued
+async fn get_job_video(pool: &DbPool, id: i32) -> AppResult<Option<Video>> {
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    match get_video_by_id(&connection, id) {
+        Ok(video) => Ok(Some(video)),
+        Err(AppError::NotFound) => {
+            info!("Video {} is gone, skipping its job", id);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn execute_job(pool: &DbPool, events: &VideoEvents, job: JobKind) -> AppResult<()> {
+    match job {
+        JobKind::ProbeVideo { video_id } => probe_upload(pool, events, video_id).await,
+        JobKind::ImportMetadata { video_id } => match get_job_video(pool, video_id).await? {
+            Some(video) => {
+                import_embedded_captions(pool, &video).await?;
+                import_chapters(pool, &video).await
+            }
+            None => Ok(()),
+        },
+        JobKind::GeneratePreviews { video_id } => match get_job_video(pool, video_id).await? {
+            Some(video) => {
+                generate_previews(pool, &video).await?;
+                events.publish(&video, VideoEventKind::PreviewsReady);
+                Ok(())
+            }
+            None => Ok(()),
+        },
+        #[cfg(feature = "transcription")]
+        JobKind::Transcribe { video_id } => match get_job_video(pool, video_id).await? {
+            Some(video) => crate::transcription::util::transcribe_video(pool, &video).await,
+            None => Ok(()),
+        },
+        JobKind::ClipVideo {
+            source_id,
+            target_id,
+            start,
+            end,
+        } => {
+            let source = get_job_video(pool, source_id).await?;
+            let target = get_job_video(pool, target_id).await?;
+            match (source, target) {
+                (Some(source), Some(target)) => {
+                    run_clip_job(pool, events, &source, &target, start, end).await
+                }
+                _ => Ok(()),
+            }
+        }
+        JobKind::PurgeTrash { retention_days } => purge_expired_videos(pool, retention_days).await,
+        JobKind::ReconcileStorage { repair } => {
+            let report = reconcile_storage_in_background(pool, repair).await?;
+            info!(
+                "Reconciled storage: {} orphaned files, {} missing files, {} stale temporary files, {} stuck videos",
+                report.orphaned_files.len(),
+                report.missing_files.len(),
+                report.stale_temp_files.len(),
+                report.stuck_videos.len()
+            );
+            Ok(())
+        }
+        JobKind::ScrubStorage {
+            max_bytes_per_second,
+        } => scrub_storage(pool, max_bytes_per_second).await,
+    }
+}
+
+/// Runs a claimed job and records the outcome, queueing it again after a delay
+/// if it failed and has attempts left
+async fn run_job(
+    pool: DbPool,
+    job: Job,
+    running: RunningJobs,
+    events: VideoEvents,
+    _permit: OwnedSemaphorePermit,
+) {
+    info!(
+        "Running job {} ({}), attempt {} of {}",
+        job.id, job.kind, job.attempts, job.max_attempts
+    );
+    let kind = serde_json::from_str::<JobKind>(&job.payload);
+    let result = match &kind {
+        Ok(kind) => execute_job(&pool, &events, kind.clone()).await,
+        Err(e) => Err(AppError::Internal(format!("invalid job payload ({})", e))),
+    };
+    running.handles().remove(&job.id);
+
+    let connection = match get_connection(&pool).await {
+        Some(connection) => connection,
+        None => {
+            warn!("Failed to record the outcome of job {}", job.id);
+            return;
+        }
+    };
+    let recorded = match result {
+        Ok(_) => finish_job(&connection, job.id, JobStatus::Succeeded, None),
+        Err(e) if job.attempts < job.max_attempts => {
+            let delay_seconds = retry_delay_seconds(job.attempts);
+            warn!(
+                "Job {} failed, retrying in {} seconds ({})",
+                job.id, delay_seconds, e
+            );
+            retry_job_later(&connection, job.id, delay_seconds, &e.to_string())
+        }
+        Err(e) => {
+            warn!("Job {} failed on its last attempt ({})", job.id, e);
+            if let Ok(kind) = &kind {
+                if let Err(e) = fail_cancelled_job(&connection, &events, kind) {
+                    warn!(
+                        "Failed to mark the video of job {} as failed ({})",
+                        job.id, e
+                    );
+                }
+            }
+            finish_job(&connection, job.id, JobStatus::Dead, Some(&e.to_string()))
+        }
+    };
+    if let Err(e) = recorded {
+        warn!("Failed to record the outcome of job {} ({})", job.id, e);
+    }
+}
+
+/// Marks the video of a cancelled or dead job as failed where the job would have finished it
+pub fn fail_cancelled_job(
+    connection: &PgConnection,
+    events: &VideoEvents,
+    kind: &JobKind,
+) -> AppResult<()> {
+    match kind {
+        JobKind::ProbeVideo { video_id } => fail_upload(connection, events, *video_id),
+        JobKind::ClipVideo {
+            source_id,
+            target_id,
+            ..
+        } => fail_clip(connection, events, *source_id, *target_id),
+        _ => Ok(()),
+    }
+}
+
+/// Claims and runs the due jobs of a queue, at most `concurrency` at a time
+async fn run_job_workers(
+    pool: DbPool,
+    running: RunningJobs,
+    events: VideoEvents,
+    queue: JobQueue,
+    concurrency: usize,
+) {
+    let permits = Arc::new(Semaphore::new(concurrency));
+    let mut interval = interval(Duration::from_millis(POLL_INTERVAL_MILLISECONDS));
+    loop {
+        interval.tick().await;
+        let free = permits.available_permits();
+        if free == 0 {
+            continue;
+        }
+        let connection = match get_connection(&pool).await {
+            Some(connection) => connection,
+            None => continue,
+        };
+        let jobs = match claim_jobs(&connection, queue.as_str(), free as i64) {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                warn!("Failed to claim {} jobs ({})", queue.as_str(), e);
+                continue;
+            }
+        };
+        drop(connection);
+
+        for job in jobs {
+            // Only this loop takes permits, so there are at least as many as claimed jobs
+            let permit = match permits.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            let mut handles = running.handles();
+            let id = job.id;
+            let handle = rocket::tokio::spawn(run_job(
+                pool.clone(),
+                job,
+                running.clone(),
+                events.clone(),
+                permit,
+            ));
+            handles.insert(id, handle);
+        }
+    }
+}
+
+/// Starts the workers of every queue, with their concurrency set by
+/// `JOB_MEDIA_CONCURRENCY` and `JOB_MAINTENANCE_CONCURRENCY`
+pub fn spawn_job_workers(pool: &DbPool, running: &RunningJobs, events: &VideoEvents) {
+    for (queue, concurrency) in [
+        (
+            JobQueue::Media,
+            env_or("JOB_MEDIA_CONCURRENCY", DEFAULT_MEDIA_CONCURRENCY),
+        ),
+        (
+            JobQueue::Maintenance,
+            env_or(
+                "JOB_MAINTENANCE_CONCURRENCY",
+                DEFAULT_MAINTENANCE_CONCURRENCY,
+            ),
+        ),
+    ] {
+        rocket::tokio::spawn(run_job_workers(
+            pool.clone(),
+            running.clone(),
+            events.clone(),
+            queue,
+            concurrency.max(1),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retry_delay_seconds, MAX_RETRY_DELAY_SECONDS, RETRY_BASE_DELAY_SECONDS};
+
+    #[test]
+    fn retry_delay_doubles_per_attempt() {
+        assert_eq!(retry_delay_seconds(1), RETRY_BASE_DELAY_SECONDS);
+        assert_eq!(retry_delay_seconds(2), RETRY_BASE_DELAY_SECONDS * 2);
+        assert_eq!(retry_delay_seconds(3), RETRY_BASE_DELAY_SECONDS * 4);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay_seconds(0), RETRY_BASE_DELAY_SECONDS);
+        assert_eq!(retry_delay_seconds(20), MAX_RETRY_DELAY_SECONDS);
+        assert_eq!(retry_delay_seconds(i32::MAX), MAX_RETRY_DELAY_SECONDS);
+    }
+}
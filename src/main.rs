@@ -11,20 +11,39 @@ extern crate diesel;
 extern crate openssl;
 
 pub mod api;
+pub mod audio;
 pub mod auth;
+pub mod caption;
+pub mod chapter;
+pub mod clip;
+pub mod collection;
+pub mod comment;
+pub mod db;
+pub mod docs;
+pub mod download;
+pub mod error;
+pub mod event;
+pub mod folder;
+pub mod job;
+pub mod media;
 pub mod models;
+pub mod playlist;
+pub mod preview;
+pub mod response;
 pub mod schema;
+pub mod search;
+pub mod storage;
+pub mod tag;
+#[cfg(feature = "transcription")]
+pub mod transcription;
+pub mod trash;
 pub mod util;
 pub mod video;
 
-use diesel::prelude::*;
 use dotenv::dotenv;
-use rocket::{fs::NamedFile, response::Redirect, routes};
+use rocket::{fs::NamedFile, response::Redirect, routes, Build, Rocket};
 use rocket_oauth2::OAuth2;
-use std::{
-    env,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 #[get("/")]
 async fn index() -> Option<NamedFile> {
@@ -44,18 +63,14 @@ async fn not_found_catcher() -> Redirect {
     Redirect::to("/404")
 }
 
-#[rocket::main]
-async fn main() {
-    dotenv().ok();
-    openssl_probe::init_ssl_cert_env_vars();
-
-    let connection = create_connection().expect("Failed to connect to database");
-
-    embedded_migrations::run(&connection).expect("Failed to run embedded migrations");
-
-    std::mem::drop(connection);
+#[catch(503)]
+async fn service_unavailable_catcher() -> crate::error::AppError {
+    crate::error::AppError::ServiceUnavailable
+}
 
-    match rocket::build()
+/// Mounts every route and catcher
+fn mount_routes(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket
         .mount("/", routes![index, files,])
         .mount(
             "/api",
@@ -65,6 +80,9 @@ async fn main() {
                 crate::api::api::get_user_by_id,
                 crate::api::api::get_all_videos,
                 crate::api::api::get_video_with_id,
+                crate::api::api::get_public_videos,
+                crate::search::public::search,
+                crate::tag::public::get_tags,
                 crate::auth::auth::me,
                 crate::auth::auth::google_login,
                 crate::auth::auth::google_callback,
@@ -73,18 +91,155 @@ async fn main() {
                 crate::auth::auth::discord_login,
                 crate::auth::auth::discord_callback,
                 crate::auth::auth::logout,
+                crate::event::public::user_events,
             ],
         )
         .mount(
             "/api/video",
             routes![
+                crate::video::public::list_videos,
                 crate::video::public::get_video,
                 crate::video::public::add_video,
                 crate::video::public::delete_video,
                 crate::video::public::get_video_info,
+                crate::video::public::edit_video,
+                crate::video::public::unlock_video,
+                crate::comment::public::get_comments,
+                crate::comment::public::add_comment,
+                crate::caption::public::get_captions,
+                crate::caption::public::upload_captions,
+                crate::caption::public::delete_captions,
+                crate::chapter::public::get_chapters_vtt,
+                crate::chapter::public::set_chapters,
+                crate::clip::public::clip_video,
+                crate::preview::public::get_thumbnail,
+                crate::preview::public::get_sprite_vtt,
+                crate::preview::public::get_preview,
+                crate::audio::public::get_audio,
+                crate::download::public::download_video,
+                crate::event::public::video_events,
+            ],
+        )
+        .mount(
+            "/api/folder",
+            routes![
+                crate::folder::public::list_folders,
+                crate::folder::public::create_folder,
+                crate::folder::public::get_folder,
+                crate::folder::public::edit_folder,
+                crate::folder::public::delete_folder,
+            ],
+        )
+        .mount(
+            "/api/collection",
+            routes![
+                crate::collection::public::list_collections,
+                crate::collection::public::create_collection,
+                crate::collection::public::get_collection,
+                crate::collection::public::edit_collection,
+                crate::collection::public::delete_collection,
+                crate::collection::public::add_collection_video,
+                crate::collection::public::remove_collection_video,
+            ],
+        )
+        .mount(
+            "/api/playlist",
+            routes![
+                crate::playlist::public::list_playlists,
+                crate::playlist::public::create_playlist,
+                crate::playlist::public::get_playlist_info,
+                crate::playlist::public::edit_playlist,
+                crate::playlist::public::delete_playlist,
+                crate::playlist::public::add_playlist_item,
+                crate::playlist::public::delete_playlist_item,
+                crate::playlist::public::reorder_playlist,
+                crate::playlist::public::create_one_time_playlist_link,
+            ],
+        )
+        .mount(
+            "/api/comment",
+            routes![
+                crate::comment::public::edit_comment,
+                crate::comment::public::resolve_comment,
+                crate::comment::public::delete_comment,
+            ],
+        )
+        .mount(
+            "/api/trash",
+            routes![
+                crate::trash::public::list_trash,
+                crate::trash::public::restore_trashed_video,
+                crate::trash::public::purge_trashed_video,
             ],
         )
-        .register("/", catchers![not_found_catcher])
+        .mount(
+            "/api/storage",
+            routes![
+                crate::storage::public::reconcile,
+                crate::storage::public::storage_health,
+            ],
+        )
+        .mount(
+            "/api/jobs",
+            routes![
+                crate::job::public::list_jobs,
+                crate::job::public::get_job,
+                crate::job::public::retry_job,
+                crate::job::public::cancel_job,
+            ],
+        )
+        .register(
+            "/",
+            catchers![not_found_catcher, service_unavailable_catcher],
+        )
+        .mount(
+            "/api/docs",
+            routes![crate::docs::public::docs, crate::docs::public::openapi_json],
+        )
+}
+
+#[rocket::main]
+async fn main() {
+    dotenv().ok();
+    openssl_probe::init_ssl_cert_env_vars();
+
+    let pool = crate::db::create_pool().expect("Failed to connect to database");
+
+    let connection = pool.get().expect("Failed to connect to database");
+
+    embedded_migrations::run(&connection).expect("Failed to run embedded migrations");
+
+    // Before the timestamps, which writing the sizes would otherwise bump
+    if let Err(e) = crate::video::sql::backfill_video_file_sizes(&connection) {
+        warn!("Failed to backfill video file sizes ({})", e);
+    }
+
+    if let Err(e) = crate::video::sql::backfill_video_timestamps(&connection) {
+        warn!("Failed to backfill video timestamps ({})", e);
+    }
+
+    match crate::job::sql::requeue_interrupted_jobs(&connection) {
+        Ok(0) => {}
+        Ok(count) => info!("Queued {} interrupted jobs again", count),
+        Err(e) => warn!("Failed to queue interrupted jobs again ({})", e),
+    }
+
+    std::mem::drop(connection);
+
+    let running_jobs = crate::job::util::RunningJobs::default();
+    let video_events = crate::event::util::VideoEvents::default();
+    crate::job::util::spawn_job_workers(&pool, &running_jobs, &video_events);
+    rocket::tokio::spawn(crate::storage::util::schedule_reconcile_job(pool.clone()));
+    rocket::tokio::spawn(crate::storage::util::schedule_scrub_job(pool.clone()));
+    rocket::tokio::spawn(crate::trash::util::schedule_purge_job(pool.clone()));
+    rocket::tokio::spawn(crate::media::util::backfill_video_hashes(pool.clone()));
+
+    match mount_routes(rocket::build())
+        .manage(pool)
+        .manage(running_jobs)
+        .manage(video_events)
+        .manage(crate::video::password::UnlockRateLimiter::default())
+        .manage(crate::audio::util::AudioExtractions::default())
         .attach(crate::util::CORS)
         .attach(OAuth2::<crate::auth::auth::Hogbisz>::fairing("hogbisz"))
         .launch()
@@ -97,11 +252,3 @@ async fn main() {
         }
     }
 }
-
-fn create_connection() -> Option<PgConnection> {
-    let database_url = unwrap_or_return_result!(env::var("DATABASE_URL"), "Database URL not set.");
-    Some(unwrap_or_return_result!(
-        PgConnection::establish(&database_url),
-        "Error connecting to database!"
-    ))
-}
@@ -0,0 +1,55 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*};
+use diesel::prelude::*;
+
+/// Adds a reference to the blob with the content of `blob`, inserting it if the
+/// content is new. The returned blob has a `ref_count` of 1 when it was inserted.
+pub fn reference_media_blob(
+    connection: &PgConnection,
+    blob: &MediaBlobNoId,
+) -> AppResult<MediaBlob> {
+    use crate::schema::media_blobs::dsl;
+
+    Ok(diesel::insert_into(crate::schema::media_blobs::table)
+        .values(blob)
+        .on_conflict(dsl::sha256)
+        .do_update()
+        .set(dsl::ref_count.eq(dsl::ref_count + 1))
+        .get_result::<MediaBlob>(connection)?)
+}
+
+/// Drops a reference to a blob, deleting it along with the last reference.
+/// Returns the deleted blob, whose file is no longer used by any video.
+pub fn release_media_blob(connection: &PgConnection, sha256: &str) -> AppResult<Option<MediaBlob>> {
+    use crate::schema::media_blobs::dsl;
+
+    let blob = diesel::update(crate::schema::media_blobs::table.filter(dsl::sha256.eq(sha256)))
+        .set(dsl::ref_count.eq(dsl::ref_count - 1))
+        .get_result::<MediaBlob>(connection)?;
+    if blob.ref_count > 0 {
+        return Ok(None);
+    }
+    diesel::delete(crate::schema::media_blobs::table.filter(dsl::id.eq(blob.id)))
+        .execute(connection)?;
+    Ok(Some(blob))
+}
+
+/// Gets the ids of a user's other videos with the content `sha256`, leaving out the trash
+pub fn get_duplicate_video_ids(
+    connection: &PgConnection,
+    owner_id: i32,
+    sha256: &str,
+    exclude_id: i32,
+) -> AppResult<Vec<String>> {
+    use crate::schema::videos::dsl;
+
+    Ok(crate::schema::videos::table
+        .filter(dsl::owner_id.eq(owner_id))
+        .filter(dsl::sha256.eq(sha256))
+        .filter(dsl::id.ne(exclude_id))
+        .filter(dsl::deleted_at.is_null())
+        .order(dsl::id.asc())
+        .select(dsl::video_id)
+        .load::<String>(connection)?)
+}
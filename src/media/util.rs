@@ -0,0 +1,178 @@
+use crate::{
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    media::sql::{reference_media_blob, release_media_blob},
+    models::{MediaBlobNoId, Video},
+    util::remove_file_if_exists,
+    video::sql::{get_unhashed_videos, set_unhashed_video_blob},
+};
+use diesel::{Connection, PgConnection};
+use rocket::tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWrite},
+    time::sleep,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Blobs are stored by content, not per owner, since any number of users can share one
+pub const MEDIA_BLOB_FOLDER: &str = "videos/blobs";
+
+const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
+pub fn media_blob_path(sha256: &str, ending: &str) -> String {
+    format!("{}/{}.{}", MEDIA_BLOB_FOLDER, sha256, ending)
+}
+
+/// Drops a video's reference to its file, once its row is deleted or points elsewhere.
+/// Returns the path of the file when no video uses it anymore.
+pub fn release_video_file(connection: &PgConnection, video: &Video) -> AppResult<Option<String>> {
+    match &video.sha256 {
+        Some(sha256) => Ok(release_media_blob(connection, sha256)?.map(|blob| blob.path)),
+        // Files from before deduplication belong to their video alone
+        None => Ok(Some(video.video_path.clone())),
+    }
+}
+
+/// Computes the hex encoded SHA-256 of a file. When `max_bytes_per_second` is given,
+/// reading is slowed down to that rate so other disk I/O is not starved.
+pub async fn hash_file(path: &str, max_bytes_per_second: Option<u64>) -> io::Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; HASH_CHUNK_SIZE];
+    let started = Instant::now();
+    let mut total_read = 0;
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        total_read += read as u64;
+        if let Some(rate) = max_bytes_per_second.filter(|rate| *rate > 0) {
+            let due = Duration::from_secs_f64(total_read as f64 / rate as f64);
+            let elapsed = started.elapsed();
+            if due > elapsed {
+                sleep(due - elapsed).await;
+            }
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Hashes the file of a video without a hash and gives it a media blob. The file
+/// becomes the blob, unless its content is already stored, then it is dropped
+/// for the stored file.
+async fn backfill_video_hash(pool: &DbPool, id: i32, video_path: &str) -> AppResult<()> {
+    let sha256 = hash_file(video_path, None).await?;
+    let file_size = fs::metadata(video_path).await?.len() as i64;
+
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    let blob = connection.transaction::<_, AppError, _>(|| {
+        let blob = reference_media_blob(
+            &connection,
+            &MediaBlobNoId {
+                sha256,
+                path: video_path.to_string(),
+                file_size,
+                ref_count: 1,
+            },
+        )?;
+        if !set_unhashed_video_blob(&connection, id, &blob)? {
+            // Changed since it was loaded, rolls back the reference
+            return Err(AppError::Conflict);
+        }
+        Ok(blob)
+    })?;
+    drop(connection);
+    if blob.path != video_path {
+        info!(
+            "Video {} has the same content as {}, removing its file {}",
+            id, blob.path, video_path
+        );
+        remove_file_if_exists(video_path).await;
+    }
+    Ok(())
+}
+
+/// Gives every video stored without a hash a media blob, see `backfill_video_hash`.
+/// Runs in the background after startup since it reads every such file. Videos
+/// whose file can not be read keep no hash and are tried again on the next startup.
+pub async fn backfill_video_hashes(pool: DbPool) {
+    let videos = match get_connection(&pool).await {
+        Some(connection) => match get_unhashed_videos(&connection) {
+            Ok(videos) => videos,
+            Err(e) => {
+                warn!("Failed to load videos without a hash ({})", e);
+                return;
+            }
+        },
+        None => return,
+    };
+    if videos.is_empty() {
+        return;
+    }
+
+    info!("Hashing the files of {} videos", videos.len());
+    for (id, video_path) in videos {
+        match backfill_video_hash(&pool, id, &video_path).await {
+            Ok(()) => {}
+            Err(AppError::Conflict) => info!("Video {} changed while it was hashed", id),
+            Err(e) => warn!(
+                "Failed to backfill the hash of video {} from {} ({})",
+                id, video_path, e
+            ),
+        }
+    }
+}
+
+/// Computes the SHA-256 of everything written through it, so uploads are hashed
+/// while they are streamed to disk
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The hex encoded hash of everything written so far
+    pub fn finish(self) -> String {
+        format!("{:x}", self.hasher.finalize())
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for HashingWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            this.hasher.update(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
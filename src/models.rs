@@ -1,7 +1,9 @@
 extern crate diesel;
 
 use crate::schema::*;
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(Serialize, Deserialize, Queryable, Identifiable, Debug)]
 #[table_name = "user_permissions"]
@@ -10,7 +12,7 @@ pub struct UserPermissions {
     pub permission: String,
 }
 
-#[derive(Identifiable, Queryable, Serialize, Deserialize, Debug, Default)]
+#[derive(Identifiable, Queryable, Serialize, Deserialize, Debug, ToSchema)]
 #[table_name = "users"]
 pub struct User {
     pub id: i32,
@@ -18,38 +20,11 @@ pub struct User {
     pub email: String,
     pub displayname: String,
     pub permissions: Vec<i32>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Only recorded for Google and Discord logins, Hogbisz logins are not tracked
+    pub last_login_at: Option<NaiveDateTime>,
 }
-impl TryFrom<&String> for User {
-    type Error = ();
-    fn try_from(user_id: &String) -> Result<Self, ()> {
-        match crate::auth::sql::get_user_by_user_id(user_id) {
-            Some(user) => Ok(user),
-            None => {
-                info!(
-                    "Failed to get user with user_id {} when converting from string",
-                    user_id
-                );
-                Err(())
-            }
-        }
-    }
-}
-impl TryFrom<i32> for User {
-    type Error = ();
-    fn try_from(user_id: i32) -> Result<Self, ()> {
-        match crate::auth::sql::get_user_by_id(user_id) {
-            Some(user) => Ok(user),
-            None => {
-                info!(
-                    "Failed to get user with id {} when converting from i32",
-                    user_id
-                );
-                Err(())
-            }
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, Insertable)]
 #[table_name = "users"]
 pub struct UserNoId {
@@ -58,7 +33,17 @@ pub struct UserNoId {
     pub displayname: String,
 }
 
-#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize)]
+#[derive(
+    Identifiable,
+    Queryable,
+    QueryableByName,
+    Associations,
+    Clone,
+    Debug,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
 #[belongs_to(User, foreign_key = "owner_id")]
 #[table_name = "videos"]
 pub struct Video {
@@ -71,6 +56,38 @@ pub struct Video {
     pub video_desc: String,
     pub owner_id: i32,
     pub thumbnail_path: Option<String>,
+    pub visibility: String,
+    #[serde(skip_serializing, default)]
+    pub password_hash: Option<String>,
+    pub file_size: i64,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub folder_id: Option<i32>,
+    /// The video this one was clipped from
+    pub source_video_id: Option<i32>,
+    pub original_filename: String,
+    pub downloads_enabled: bool,
+    /// When the video was moved to the trash
+    pub deleted_at: Option<NaiveDateTime>,
+    /// The hash of the uploaded file, identifying its `MediaBlob`
+    pub sha256: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub thumbnail_sha256: Option<String>,
+}
+
+/// The columns of a video its owner can edit. Everything else is written by
+/// background jobs, which may be running while the owner edits.
+#[derive(AsChangeset, Debug)]
+#[table_name = "videos"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct VideoEdit {
+    pub video_name: String,
+    pub video_desc: String,
+    pub visibility: String,
+    pub password_hash: Option<String>,
+    pub folder_id: Option<i32>,
+    pub downloads_enabled: bool,
 }
 
 #[derive(Insertable, Debug, Serialize, Deserialize)]
@@ -84,4 +101,353 @@ pub struct VideoNoId {
     pub video_desc: String,
     pub owner_id: i32,
     pub thumbnail_path: Option<String>,
+    pub visibility: String,
+    #[serde(skip_serializing, default)]
+    pub password_hash: Option<String>,
+    pub file_size: i64,
+    pub status: String,
+    pub folder_id: Option<i32>,
+    pub source_video_id: Option<i32>,
+    pub original_filename: String,
+    pub downloads_enabled: bool,
+    pub sha256: Option<String>,
+}
+
+/// A stored file, shared by every video with the same content
+#[derive(Identifiable, Queryable, Debug, Serialize, Deserialize)]
+#[table_name = "media_blobs"]
+pub struct MediaBlob {
+    pub id: i32,
+    pub sha256: String,
+    pub path: String,
+    pub file_size: i64,
+    pub ref_count: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "media_blobs"]
+pub struct MediaBlobNoId {
+    pub sha256: String,
+    pub path: String,
+    pub file_size: i64,
+    pub ref_count: i32,
+}
+
+/// Work queued to run outside of a request, see `crate::job`
+#[derive(Identifiable, Queryable, QueryableByName, Debug, Serialize, Deserialize, ToSchema)]
+#[table_name = "jobs"]
+pub struct Job {
+    pub id: i32,
+    pub queue: String,
+    pub kind: String,
+    /// The job's arguments as JSON
+    pub payload: String,
+    /// One of `queued`, `running`, `succeeded`, `dead` or `cancelled`
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "jobs"]
+pub struct JobNoId {
+    pub queue: String,
+    pub kind: String,
+    pub payload: String,
+    pub max_attempts: i32,
+}
+
+/// The outcome of the last scrub of a stored file
+#[derive(Identifiable, Queryable, Debug, Serialize, Deserialize, ToSchema)]
+#[table_name = "storage_health"]
+pub struct StorageHealth {
+    #[serde(skip_serializing)]
+    pub id: i32,
+    /// Either `media` or `thumbnail`
+    pub kind: String,
+    pub path: String,
+    /// Missing for the file of a video that has not been hashed yet
+    pub expected_sha256: Option<String>,
+    /// The hash the file had, missing if the file is gone
+    pub actual_sha256: Option<String>,
+    /// One of `ok`, `missing` or `corrupt`
+    pub status: String,
+    pub checked_at: NaiveDateTime,
+}
+
+#[derive(Insertable, AsChangeset, Debug, Serialize, Deserialize)]
+#[table_name = "storage_health"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct StorageHealthNoId {
+    pub kind: String,
+    pub path: String,
+    pub expected_sha256: Option<String>,
+    pub actual_sha256: Option<String>,
+    pub status: String,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "video_downloads"]
+pub struct VideoDownloadNoId {
+    pub video_id: i32,
+    pub user_id: Option<i32>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize)]
+#[belongs_to(Video, foreign_key = "video_id")]
+#[table_name = "video_shares"]
+pub struct VideoShare {
+    pub id: i32,
+    pub video_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "video_shares"]
+pub struct VideoShareNoId {
+    pub video_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(
+    Identifiable, Queryable, AsChangeset, Associations, Debug, Serialize, Deserialize, ToSchema,
+)]
+#[belongs_to(User, foreign_key = "owner_id")]
+#[table_name = "folders"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct Folder {
+    pub id: i32,
+    pub folder_id: String,
+    pub owner_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "folders"]
+pub struct FolderNoId {
+    pub folder_id: String,
+    pub owner_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize)]
+#[belongs_to(Folder, foreign_key = "folder_id")]
+#[table_name = "folder_shares"]
+pub struct FolderShare {
+    pub id: i32,
+    pub folder_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "folder_shares"]
+pub struct FolderShareNoId {
+    pub folder_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(
+    Identifiable, Queryable, AsChangeset, Associations, Debug, Serialize, Deserialize, ToSchema,
+)]
+#[belongs_to(User, foreign_key = "owner_id")]
+#[table_name = "collections"]
+pub struct Collection {
+    pub id: i32,
+    pub collection_id: String,
+    pub owner_id: i32,
+    pub name: String,
+    pub description: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "collections"]
+pub struct CollectionNoId {
+    pub collection_id: String,
+    pub owner_id: i32,
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "collection_videos"]
+pub struct CollectionVideoNoId {
+    pub collection_id: i32,
+    pub video_id: i32,
+}
+
+#[derive(
+    Identifiable, Queryable, AsChangeset, Associations, Debug, Serialize, Deserialize, ToSchema,
+)]
+#[belongs_to(User, foreign_key = "owner_id")]
+#[table_name = "playlists"]
+pub struct Playlist {
+    pub id: i32,
+    pub playlist_id: String,
+    pub owner_id: i32,
+    pub title: String,
+    pub description: String,
+    pub visibility: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "playlists"]
+pub struct PlaylistNoId {
+    pub playlist_id: String,
+    pub owner_id: i32,
+    pub title: String,
+    pub description: String,
+    pub visibility: String,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize, ToSchema)]
+#[belongs_to(Playlist, foreign_key = "playlist_id")]
+#[table_name = "playlist_items"]
+pub struct PlaylistItem {
+    pub id: i32,
+    pub playlist_id: i32,
+    pub video_id: i32,
+    pub position: i32,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "playlist_items"]
+pub struct PlaylistItemNoId {
+    pub playlist_id: i32,
+    pub video_id: i32,
+    pub position: i32,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "playlist_shares"]
+pub struct PlaylistShareNoId {
+    pub playlist_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize)]
+#[belongs_to(Playlist, foreign_key = "playlist_id")]
+#[table_name = "one_time_playlist"]
+pub struct OneTimePlaylist {
+    pub id: i32,
+    pub playlist_id: i32,
+    pub created_at: NaiveDateTime,
+    pub one_time_pass: String,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "one_time_playlist"]
+pub struct OneTimePlaylistNoId {
+    pub playlist_id: i32,
+    pub one_time_pass: String,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize)]
+#[belongs_to(User, foreign_key = "owner_id")]
+#[table_name = "tags"]
+pub struct Tag {
+    pub id: i32,
+    pub owner_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "tags"]
+pub struct TagNoId {
+    pub owner_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "video_tags"]
+pub struct VideoTag {
+    pub video_id: i32,
+    pub tag_id: i32,
+}
+
+#[derive(
+    Identifiable, Queryable, AsChangeset, Associations, Debug, Serialize, Deserialize, ToSchema,
+)]
+#[belongs_to(Video, foreign_key = "video_id")]
+#[table_name = "comments"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct Comment {
+    pub id: i32,
+    pub video_id: i32,
+    pub author_id: i32,
+    pub timestamp_seconds: f64,
+    pub body: String,
+    pub parent_id: Option<i32>,
+    pub resolved: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "comments"]
+pub struct CommentNoId {
+    pub video_id: i32,
+    pub author_id: i32,
+    pub timestamp_seconds: f64,
+    pub body: String,
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize, ToSchema)]
+#[belongs_to(Video, foreign_key = "video_id")]
+#[table_name = "caption_tracks"]
+pub struct CaptionTrack {
+    pub id: i32,
+    pub video_id: i32,
+    pub language: String,
+    pub label: String,
+    pub source: String,
+    /// The track as a WebVTT file
+    #[serde(skip_serializing, default)]
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, AsChangeset, Debug, Serialize, Deserialize)]
+#[table_name = "caption_tracks"]
+pub struct CaptionTrackNoId {
+    pub video_id: i32,
+    pub language: String,
+    pub label: String,
+    pub source: String,
+    pub content: String,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug, Serialize, Deserialize, ToSchema)]
+#[belongs_to(Video, foreign_key = "video_id")]
+#[table_name = "chapters"]
+pub struct Chapter {
+    #[serde(skip_serializing)]
+    pub id: i32,
+    #[serde(skip_serializing)]
+    pub video_id: i32,
+    pub start_seconds: f64,
+    pub title: String,
+}
+
+#[derive(Insertable, Debug, Serialize, Deserialize)]
+#[table_name = "chapters"]
+pub struct ChapterNoId {
+    pub video_id: i32,
+    pub start_seconds: f64,
+    pub title: String,
 }
@@ -0,0 +1,59 @@
+use crate::{
+    models::{Playlist, Video},
+    video::{model::Visibility, util::VideoAccess},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewPlaylist {
+    pub title: String,
+    pub description: Option<String>,
+    pub visibility: Option<Visibility>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PlaylistInfo {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub visibility: Option<Visibility>,
+    pub share: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewPlaylistItem {
+    /// The `video_id` of the video to add
+    pub video: String,
+    /// Where to insert the video, at the end if None
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PlaylistOrder {
+    /// Every listed item id of the playlist in the new order, items of trashed videos stay last
+    pub items: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlaylistItemDetails {
+    pub id: i32,
+    pub position: i32,
+    /// The caller's access to the video
+    pub access: VideoAccess,
+    /// None unless the caller can watch the video, which for a password protected
+    /// video means it has been unlocked
+    pub video: Option<Video>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlaylistDetails {
+    pub playlist: Playlist,
+    pub items: Vec<PlaylistItemDetails>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OneTimePlaylistLink {
+    /// The pass, valid for a single view of the playlist
+    pub one_time: String,
+    pub url: String,
+}
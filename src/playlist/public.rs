@@ -0,0 +1,400 @@
+use crate::{
+    auth::{
+        sql::get_user_by_user_id,
+        util::{get_user_from_cookies, user_has_admin_permission},
+    },
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::{Playlist, PlaylistItem, PlaylistNoId},
+    playlist::{
+        model::{
+            NewPlaylist, NewPlaylistItem, OneTimePlaylistLink, PlaylistDetails, PlaylistInfo,
+            PlaylistItemDetails, PlaylistOrder,
+        },
+        sql::{
+            delete_playlist_with_id, generate_new_playlist_id, get_playlist_by_playlist_id,
+            get_playlist_items, get_playlists_for_user, insert_one_time_playlist, insert_playlist,
+            insert_playlist_item, insert_playlist_share, remove_playlist_item,
+            reorder_playlist_items, update_playlist, use_one_time_playlist_pass,
+        },
+        util::user_can_view_playlist,
+    },
+    response::{ApiMessage, ApiMessageResult, ApiResponse, ApiResult},
+    video::{
+        sql::get_video_by_video_id,
+        util::{get_video_access, user_can_view_video, VideoAccess},
+    },
+};
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use rocket::State;
+
+const MAX_PLAYLIST_TITLE_LENGTH: usize = 128;
+const MAX_PLAYLIST_DESCRIPTION_LENGTH: usize = 1024;
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    responses((status = 200, body = PlaylistListResponse))
+)]
+#[get("/")]
+pub async fn list_playlists(
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<Playlist>> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let playlists = get_playlists_for_user(&connection, user.id)?;
+
+    Ok(ApiResponse::ok(playlists))
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    request_body = NewPlaylist,
+    responses((status = 200, body = PlaylistResponse))
+)]
+#[post("/", data = "<playlist>", format = "json")]
+pub async fn create_playlist(
+    playlist: Json<NewPlaylist>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Playlist> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut title = playlist.title.trim().to_string();
+    if title.is_empty() {
+        info!("Playlist title is empty");
+        return Err(AppError::BadRequest);
+    }
+    if title.len() > MAX_PLAYLIST_TITLE_LENGTH {
+        info!("Title too long. Cutting off at 128 characters");
+        title.truncate(MAX_PLAYLIST_TITLE_LENGTH);
+    }
+    let mut description = playlist.description.clone().unwrap_or_default();
+    if description.len() > MAX_PLAYLIST_DESCRIPTION_LENGTH {
+        info!("Description too long. Cutting off at 1024 characters");
+        description.truncate(MAX_PLAYLIST_DESCRIPTION_LENGTH);
+    }
+
+    let playlist = insert_playlist(
+        &connection,
+        &PlaylistNoId {
+            playlist_id: generate_new_playlist_id(&connection)?,
+            owner_id: user.id,
+            title,
+            description,
+            visibility: playlist.visibility.unwrap_or_default().as_str().to_string(),
+        },
+    )?;
+
+    Ok(ApiResponse::ok(playlist))
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    responses((status = 200, body = PlaylistDetailsResponse))
+)]
+#[get("/<id>?<one_time>")]
+pub async fn get_playlist_info(
+    id: String,
+    one_time: Option<String>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<PlaylistDetails> {
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let playlist = get_playlist_by_playlist_id(&connection, &id)?;
+
+    if !user_can_view_playlist(&connection, &playlist, user.as_ref())? {
+        let used_one_time_pass = match &one_time {
+            Some(one_time) => use_one_time_playlist_pass(&connection, playlist.id, one_time)?,
+            None => false,
+        };
+        if !used_one_time_pass {
+            return match user {
+                Some(_) => Err(AppError::Forbidden),
+                None => Err(AppError::Unauthorized),
+            };
+        }
+    }
+
+    let items = get_playlist_items(&connection, playlist.id)?;
+
+    let items = items
+        .into_iter()
+        .map(|(item, video)| {
+            let access = get_video_access(&connection, &video, user.as_ref(), cookies)?;
+            Ok(PlaylistItemDetails {
+                id: item.id,
+                position: item.position,
+                access,
+                video: match access {
+                    VideoAccess::Allowed => Some(video),
+                    VideoAccess::PasswordRequired | VideoAccess::Denied => None,
+                },
+            })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    Ok(ApiResponse::ok(PlaylistDetails { playlist, items }))
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    request_body = PlaylistInfo,
+    responses((status = 200, body = PlaylistResponse))
+)]
+#[post("/edit?<id>", data = "<info>", format = "json")]
+pub async fn edit_playlist(
+    id: String,
+    info: Json<PlaylistInfo>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Playlist> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let mut playlist = get_playlist_by_playlist_id(&connection, &id)?;
+
+    if playlist.owner_id != user.id {
+        info!(
+            "User {} is not the owner of playlist {}",
+            user.id, playlist.id
+        );
+        return Err(AppError::Forbidden);
+    }
+
+    if let Some(title) = &info.title {
+        let mut title = title.trim().to_string();
+        if title.is_empty() {
+            return Err(AppError::BadRequest);
+        }
+        if title.len() > MAX_PLAYLIST_TITLE_LENGTH {
+            info!("Title too long. Cutting off at 128 characters");
+            title.truncate(MAX_PLAYLIST_TITLE_LENGTH);
+        }
+        playlist.title = title;
+    }
+
+    if let Some(description) = &info.description {
+        let mut description = description.clone();
+        if description.len() > MAX_PLAYLIST_DESCRIPTION_LENGTH {
+            info!("Description too long. Cutting off at 1024 characters");
+            description.truncate(MAX_PLAYLIST_DESCRIPTION_LENGTH);
+        }
+        playlist.description = description;
+    }
+
+    if let Some(visibility) = info.visibility {
+        playlist.visibility = visibility.as_str().to_string();
+    }
+
+    if let Some(shared_ids) = &info.share {
+        for id in shared_ids {
+            let user_share = match get_user_by_user_id(&connection, id) {
+                Ok(u) => u,
+                Err(AppError::NotFound) => {
+                    info!("No user found with user_id {}", id);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if user_share.id == user.id {
+                info!(
+                    "User {} is trying to share playlist {} with themselves",
+                    user.id, playlist.id
+                );
+                continue;
+            }
+            insert_playlist_share(&connection, playlist.id, user_share.id)?;
+        }
+    }
+
+    let playlist = update_playlist(&connection, &playlist)?;
+
+    Ok(ApiResponse::ok(playlist))
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>")]
+pub async fn delete_playlist(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let playlist = get_playlist_by_playlist_id(&connection, &id)?;
+
+    if playlist.owner_id != user.id && !user_has_admin_permission(&user) {
+        info!("User did not own playlist that was attempted to be deleted.");
+        return Err(AppError::Forbidden);
+    }
+
+    delete_playlist_with_id(&connection, playlist.id)?;
+
+    Ok(ApiMessage::ok())
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    request_body = NewPlaylistItem,
+    responses((status = 200, body = PlaylistItemResponse))
+)]
+#[post("/<id>/items", data = "<item>", format = "json")]
+pub async fn add_playlist_item(
+    id: String,
+    item: Json<NewPlaylistItem>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<PlaylistItem> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let playlist = get_playlist_by_playlist_id(&connection, &id)?;
+    if playlist.owner_id != user.id {
+        return Err(AppError::Forbidden);
+    }
+
+    let video = match get_video_by_video_id(&connection, &item.video) {
+        Ok(video) => video,
+        Err(AppError::NotFound) => return Err(AppError::BadRequest),
+        Err(e) => return Err(e),
+    };
+    if !user_can_view_video(&connection, &video, Some(&user))? {
+        info!("User {} can not view video {}", user.id, video.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let item = insert_playlist_item(&connection, playlist.id, video.id, item.position)?;
+
+    Ok(ApiResponse::ok(item))
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>/items/<item_id>")]
+pub async fn delete_playlist_item(
+    id: String,
+    item_id: i32,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let playlist = get_playlist_by_playlist_id(&connection, &id)?;
+    if playlist.owner_id != user.id {
+        return Err(AppError::Forbidden);
+    }
+
+    remove_playlist_item(&connection, playlist.id, item_id)?;
+
+    Ok(ApiMessage::ok())
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    request_body = PlaylistOrder,
+    responses((status = 200, body = ApiMessage))
+)]
+#[post("/<id>/reorder", data = "<order>", format = "json")]
+pub async fn reorder_playlist(
+    id: String,
+    order: Json<PlaylistOrder>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let playlist = get_playlist_by_playlist_id(&connection, &id)?;
+    if playlist.owner_id != user.id {
+        return Err(AppError::Forbidden);
+    }
+
+    reorder_playlist_items(&connection, playlist.id, &order.items)?;
+
+    Ok(ApiMessage::ok())
+}
+
+#[utoipa::path(
+    context_path = "/api/playlist",
+    tag = "playlist",
+    responses((status = 200, body = OneTimePlaylistLinkResponse))
+)]
+#[post("/<id>/one_time")]
+pub async fn create_one_time_playlist_link(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<OneTimePlaylistLink> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let playlist = get_playlist_by_playlist_id(&connection, &id)?;
+    if playlist.owner_id != user.id {
+        return Err(AppError::Forbidden);
+    }
+
+    let one_time = insert_one_time_playlist(&connection, playlist.id)?;
+
+    Ok(ApiResponse::ok(OneTimePlaylistLink {
+        url: format!(
+            "/api/playlist/{}?one_time={}",
+            playlist.playlist_id, one_time.one_time_pass
+        ),
+        one_time: one_time.one_time_pass,
+    }))
+}
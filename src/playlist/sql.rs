@@ -0,0 +1,247 @@
+extern crate diesel;
+
+use crate::{
+    error::{AppError, AppResult},
+    models::*,
+    util::make_random_string,
+};
+use diesel::{
+    dsl::{now, IntervalDsl},
+    prelude::*,
+};
+
+pub fn get_playlist_by_playlist_id(connection: &PgConnection, id: &String) -> AppResult<Playlist> {
+    Ok(crate::schema::playlists::table
+        .filter(crate::schema::playlists::dsl::playlist_id.eq(id.to_owned()))
+        .first::<Playlist>(connection)?)
+}
+
+fn get_playlist_by_playlist_id_no_error(
+    connection: &PgConnection,
+    id: &String,
+) -> AppResult<Option<Playlist>> {
+    Ok(crate::schema::playlists::table
+        .filter(crate::schema::playlists::dsl::playlist_id.eq(id.to_owned()))
+        .first::<Playlist>(connection)
+        .optional()?)
+}
+
+pub fn get_playlists_for_user(
+    connection: &PgConnection,
+    owner_id: i32,
+) -> AppResult<Vec<Playlist>> {
+    Ok(crate::schema::playlists::table
+        .filter(crate::schema::playlists::dsl::owner_id.eq(owner_id))
+        .order(crate::schema::playlists::dsl::title.asc())
+        .load::<Playlist>(connection)?)
+}
+
+pub fn insert_playlist(connection: &PgConnection, playlist: &PlaylistNoId) -> AppResult<Playlist> {
+    Ok(diesel::insert_into(crate::schema::playlists::table)
+        .values(playlist)
+        .get_result::<Playlist>(connection)?)
+}
+
+pub fn update_playlist(connection: &PgConnection, playlist: &Playlist) -> AppResult<Playlist> {
+    Ok(diesel::update(
+        crate::schema::playlists::table.filter(crate::schema::playlists::dsl::id.eq(playlist.id)),
+    )
+    .set(playlist)
+    .get_result::<Playlist>(connection)?)
+}
+
+pub fn delete_playlist_with_id(connection: &PgConnection, id: i32) -> AppResult<()> {
+    diesel::delete(
+        crate::schema::playlists::table.filter(crate::schema::playlists::dsl::id.eq(id)),
+    )
+    .execute(connection)?;
+    Ok(())
+}
+
+/// Gets the items of a playlist with their videos, in playback order
+pub fn get_playlist_items(
+    connection: &PgConnection,
+    playlist_id: i32,
+) -> AppResult<Vec<(PlaylistItem, Video)>> {
+    use crate::schema::{playlist_items, videos};
+
+    Ok(playlist_items::table
+        .inner_join(videos::table)
+        .filter(playlist_items::dsl::playlist_id.eq(playlist_id))
+        .filter(videos::dsl::deleted_at.is_null())
+        .order(playlist_items::dsl::position.asc())
+        .load::<(PlaylistItem, Video)>(connection)?)
+}
+
+/// Inserts a video at `position` (clamped to the playlist length), moving later items
+/// back. Appends the video if no position is given.
+pub fn insert_playlist_item(
+    connection: &PgConnection,
+    playlist_id: i32,
+    video_id: i32,
+    position: Option<i32>,
+) -> AppResult<PlaylistItem> {
+    use crate::schema::playlist_items::dsl;
+
+    Ok(connection.transaction::<_, diesel::result::Error, _>(|| {
+        let length = crate::schema::playlist_items::table
+            .filter(dsl::playlist_id.eq(playlist_id))
+            .count()
+            .get_result::<i64>(connection)? as i32;
+        let position = position.unwrap_or(length).clamp(0, length);
+
+        diesel::update(
+            crate::schema::playlist_items::table
+                .filter(dsl::playlist_id.eq(playlist_id))
+                .filter(dsl::position.ge(position)),
+        )
+        .set(dsl::position.eq(dsl::position + 1))
+        .execute(connection)?;
+
+        diesel::insert_into(crate::schema::playlist_items::table)
+            .values(&PlaylistItemNoId {
+                playlist_id,
+                video_id,
+                position,
+            })
+            .get_result::<PlaylistItem>(connection)
+    })?)
+}
+
+/// Removes an item from a playlist, moving later items forward
+pub fn remove_playlist_item(
+    connection: &PgConnection,
+    playlist_id: i32,
+    item_id: i32,
+) -> AppResult<()> {
+    use crate::schema::playlist_items::dsl;
+
+    connection.transaction::<_, diesel::result::Error, _>(|| {
+        let item = diesel::delete(
+            crate::schema::playlist_items::table
+                .filter(dsl::playlist_id.eq(playlist_id))
+                .filter(dsl::id.eq(item_id)),
+        )
+        .get_result::<PlaylistItem>(connection)?;
+
+        diesel::update(
+            crate::schema::playlist_items::table
+                .filter(dsl::playlist_id.eq(playlist_id))
+                .filter(dsl::position.gt(item.position)),
+        )
+        .set(dsl::position.eq(dsl::position - 1))
+        .execute(connection)
+    })?;
+    Ok(())
+}
+
+/// Sets the order of a playlist. `item_ids` has to contain every item of the playlist
+/// exactly once, leaving out items whose video is in the trash since they are not listed.
+/// Those keep their relative order after the listed items.
+pub fn reorder_playlist_items(
+    connection: &PgConnection,
+    playlist_id: i32,
+    item_ids: &[i32],
+) -> AppResult<()> {
+    use crate::schema::{playlist_items, videos};
+
+    connection.transaction::<_, AppError, _>(|| {
+        let items = playlist_items::table
+            .inner_join(videos::table)
+            .filter(playlist_items::dsl::playlist_id.eq(playlist_id))
+            .order(playlist_items::dsl::position.asc())
+            .select((playlist_items::dsl::id, videos::dsl::deleted_at.is_null()))
+            .load::<(i32, bool)>(connection)?;
+        let mut current_ids = items
+            .iter()
+            .filter(|(_, listed)| *listed)
+            .map(|(id, _)| *id)
+            .collect::<Vec<i32>>();
+        let mut new_ids = item_ids.to_vec();
+        current_ids.sort_unstable();
+        new_ids.sort_unstable();
+        if current_ids != new_ids {
+            return Err(AppError::BadRequest);
+        }
+
+        let hidden_ids = items
+            .iter()
+            .filter(|(_, listed)| !listed)
+            .map(|(id, _)| *id);
+        for (position, item_id) in item_ids.iter().copied().chain(hidden_ids).enumerate() {
+            diesel::update(playlist_items::table.filter(playlist_items::dsl::id.eq(item_id)))
+                .set(playlist_items::dsl::position.eq(position as i32))
+                .execute(connection)?;
+        }
+        Ok(())
+    })
+}
+
+pub fn insert_playlist_share(
+    connection: &PgConnection,
+    playlist_id: i32,
+    user_id: i32,
+) -> AppResult<()> {
+    diesel::insert_into(crate::schema::playlist_shares::table)
+        .values(&PlaylistShareNoId {
+            playlist_id,
+            user_id,
+        })
+        .on_conflict_do_nothing()
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn playlist_is_shared_with_user(
+    connection: &PgConnection,
+    playlist_id: i32,
+    user_id: i32,
+) -> AppResult<bool> {
+    use crate::schema::playlist_shares::dsl;
+
+    Ok(diesel::select(diesel::dsl::exists(
+        crate::schema::playlist_shares::table
+            .filter(dsl::playlist_id.eq(playlist_id))
+            .filter(dsl::user_id.eq(user_id)),
+    ))
+    .get_result::<bool>(connection)?)
+}
+
+pub fn insert_one_time_playlist(
+    connection: &PgConnection,
+    playlist_id: i32,
+) -> AppResult<OneTimePlaylist> {
+    Ok(diesel::insert_into(crate::schema::one_time_playlist::table)
+        .values(&OneTimePlaylistNoId {
+            playlist_id,
+            one_time_pass: make_random_string(32),
+        })
+        .get_result::<OneTimePlaylist>(connection)?)
+}
+
+/// Consumes a one time pass for a playlist. Passes are valid once, for a day after creation.
+pub fn use_one_time_playlist_pass(
+    connection: &PgConnection,
+    playlist_id: i32,
+    one_time_pass: &String,
+) -> AppResult<bool> {
+    use crate::schema::one_time_playlist::dsl;
+
+    let deleted = diesel::delete(
+        crate::schema::one_time_playlist::table
+            .filter(dsl::playlist_id.eq(playlist_id))
+            .filter(dsl::one_time_pass.eq(one_time_pass))
+            .filter(dsl::created_at.gt(now - 1.days())),
+    )
+    .execute(connection)?;
+    Ok(deleted > 0)
+}
+
+/// Generates a new playlist id that does not exist in the database
+pub fn generate_new_playlist_id(connection: &PgConnection) -> AppResult<String> {
+    let mut playlist_id = make_random_string(32);
+    while get_playlist_by_playlist_id_no_error(connection, &playlist_id)?.is_some() {
+        playlist_id = make_random_string(32);
+    }
+    Ok(playlist_id)
+}
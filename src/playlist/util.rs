@@ -0,0 +1,38 @@
+use crate::{
+    auth::util::user_has_admin_permission,
+    error::AppResult,
+    models::{Playlist, User},
+    playlist::sql::playlist_is_shared_with_user,
+    video::model::Visibility,
+};
+use diesel::PgConnection;
+
+/// Checks whether `user` (None for anonymous requests) may view a playlist. Playlists
+/// follow the same visibility rules as videos, but each video in a playlist keeps its own access.
+pub fn user_can_view_playlist(
+    connection: &PgConnection,
+    playlist: &Playlist,
+    user: Option<&User>,
+) -> AppResult<bool> {
+    if let Some(user) = user {
+        if playlist.owner_id == user.id
+            || user_has_admin_permission(user)
+            || playlist_is_shared_with_user(connection, playlist.id, user.id)?
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(match playlist.visibility.parse::<Visibility>() {
+        Ok(Visibility::Private) => false,
+        Ok(Visibility::Unlisted) | Ok(Visibility::Public) => true,
+        Ok(Visibility::Internal) => user.is_some(),
+        Err(_) => {
+            warn!(
+                "Playlist {} has unknown visibility {}",
+                playlist.playlist_id, playlist.visibility
+            );
+            false
+        }
+    })
+}
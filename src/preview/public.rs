@@ -0,0 +1,93 @@
+use crate::{
+    auth::util::get_user_from_cookies,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::Video,
+    preview::util::{preview_folder, sprite_vtt, SpriteLayout, PREVIEW_FILES},
+    video::{
+        sql::get_video_by_video_id,
+        util::{get_video_access, VideoAccess},
+    },
+};
+use rocket::{
+    fs::NamedFile,
+    http::{ContentType, CookieJar},
+    State,
+};
+
+/// Gets a video the request is allowed to watch
+async fn get_viewable_video(
+    pool: &DbPool,
+    id: &String,
+    cookies: &CookieJar<'_>,
+) -> AppResult<Video> {
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    let video = get_video_by_video_id(&connection, id)?;
+    if get_video_access(&connection, &video, user.as_ref(), cookies)? != VideoAccess::Allowed {
+        return Err(AppError::NotFound);
+    }
+    Ok(video)
+}
+
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "preview",
+    responses((status = 200, description = "The thumbnail image"))
+)]
+#[get("/<id>/thumbnail")]
+pub async fn get_thumbnail(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> AppResult<NamedFile> {
+    let video = get_viewable_video(pool, &id, cookies).await?;
+    let thumbnail_path = video.thumbnail_path.ok_or(AppError::NotFound)?;
+    NamedFile::open(thumbnail_path)
+        .await
+        .map_err(AppError::from_file_error)
+}
+
+/// The WebVTT thumbnails track for seek bar previews, pointing into `sprite.jpg`
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "preview",
+    responses((status = 200, description = "Seek bar sprite sheet coordinates as WebVTT", body = String, content_type = "text/vtt"))
+)]
+#[get("/<id>/previews/sprite.vtt")]
+pub async fn get_sprite_vtt(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> AppResult<(ContentType, String)> {
+    let video = get_viewable_video(pool, &id, cookies).await?;
+    let layout = SpriteLayout::for_length(video.video_length).ok_or(AppError::NotFound)?;
+    Ok((
+        ContentType::new("text", "vtt"),
+        sprite_vtt(&layout, video.video_length),
+    ))
+}
+
+/// Serves `sprite.jpg`, `preview.webp` and `preview.mp4`
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "preview",
+    responses((status = 200, description = "A sprite sheet or looping preview file"))
+)]
+#[get("/<id>/previews/<file>", rank = 1)]
+pub async fn get_preview(
+    id: String,
+    file: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> AppResult<NamedFile> {
+    if !PREVIEW_FILES.contains(&file.as_str()) {
+        return Err(AppError::NotFound);
+    }
+    let video = get_viewable_video(pool, &id, cookies).await?;
+    NamedFile::open(format!("{}/{}", preview_folder(&video), file))
+        .await
+        .map_err(AppError::from_file_error)
+}
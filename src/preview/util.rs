@@ -0,0 +1,240 @@
+use crate::{
+    caption::util::format_vtt_timestamp,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    media::util::hash_file,
+    models::Video,
+    video::sql::set_video_thumbnail,
+};
+use rocket::tokio::fs;
+use tokio::process::Command;
+
+/// Files generated for each video, all stored in `preview_folder`
+pub const PREVIEW_FILES: [&str; 3] = ["sprite.jpg", "preview.webp", "preview.mp4"];
+
+const SPRITE_TILE_WIDTH: u32 = 160;
+const SPRITE_TILE_HEIGHT: u32 = 90;
+const SPRITE_COLUMNS: u32 = 10;
+const MAX_SPRITE_FRAMES: u32 = 100;
+const MIN_SPRITE_INTERVAL_SECONDS: f64 = 2.0;
+const PREVIEW_LENGTH_SECONDS: f64 = 3.0;
+
+pub fn preview_folder(video: &Video) -> String {
+    format!("previews/{}/{}", video.owner_id, video.video_id)
+}
+
+/// Where each frame of a video's sprite sheet is taken, and how the sheet is laid out
+#[derive(Debug, Clone, Copy)]
+pub struct SpriteLayout {
+    pub interval: f64,
+    pub frames: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl SpriteLayout {
+    /// Spreads up to `MAX_SPRITE_FRAMES` frames over the video. Videos of unknown length get no sprite sheet.
+    pub fn for_length(video_length: f64) -> Option<Self> {
+        if video_length <= 0.0 {
+            return None;
+        }
+        let interval = (video_length / MAX_SPRITE_FRAMES as f64).max(MIN_SPRITE_INTERVAL_SECONDS);
+        let frames = ((video_length / interval).ceil() as u32).clamp(1, MAX_SPRITE_FRAMES);
+        let columns = frames.min(SPRITE_COLUMNS);
+        Some(SpriteLayout {
+            interval,
+            frames,
+            columns,
+            rows: frames.div_ceil(columns),
+        })
+    }
+}
+
+/// Makes the WebVTT thumbnails track pointing into `sprite.jpg` for seek bar previews
+pub fn sprite_vtt(layout: &SpriteLayout, video_length: f64) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    for frame in 0..layout.frames {
+        let start = frame as f64 * layout.interval;
+        let end = (start + layout.interval).min(video_length);
+        vtt.push_str(&format!(
+            "\n{} --> {}\nsprite.jpg#xywh={},{},{},{}\n",
+            format_vtt_timestamp(start),
+            format_vtt_timestamp(end),
+            (frame % layout.columns) * SPRITE_TILE_WIDTH,
+            (frame / layout.columns) * SPRITE_TILE_HEIGHT,
+            SPRITE_TILE_WIDTH,
+            SPRITE_TILE_HEIGHT
+        ));
+    }
+    vtt
+}
+
+async fn run_ffmpeg(video: &Video, args: &[&str], output: &str) -> AppResult<()> {
+    let result = Command::new("ffmpeg")
+        .args(["-v", "error", "-y"])
+        .args(args)
+        .arg(output)
+        // A cancelled job stops ffmpeg with it
+        .kill_on_drop(true)
+        .output()
+        .await?;
+    if !result.status.success() {
+        return Err(AppError::Probe(format!(
+            "ffmpeg failed to make {} for video {}: {}",
+            output,
+            video.video_id,
+            String::from_utf8_lossy(&result.stderr)
+        )));
+    }
+    Ok(())
+}
+
+/// Generates a video's thumbnail, seek bar sprite sheet and short looping previews.
+/// Every file is attempted, a failure leaves that file missing and is returned at the end.
+pub async fn generate_previews(pool: &DbPool, video: &Video) -> AppResult<()> {
+    let folder = preview_folder(video);
+    fs::create_dir_all(&folder).await?;
+
+    // Skip black intros, but stay inside very short videos
+    let still_at = if video.video_length > 0.0 {
+        (video.video_length / 2.0).min(1.0)
+    } else {
+        0.0
+    };
+    let thumbnail_path = format!("{}/thumbnail.jpg", folder);
+    let thumbnail = async {
+        run_ffmpeg(
+            video,
+            &[
+                "-ss",
+                &still_at.to_string(),
+                "-i",
+                &video.video_path,
+                "-frames:v",
+                "1",
+                "-vf",
+                "scale=640:-2",
+            ],
+            &thumbnail_path,
+        )
+        .await?;
+        // Recorded for the storage scrub to verify the thumbnail against
+        let thumbnail_sha256 = hash_file(&thumbnail_path, None).await?;
+        let connection = get_connection(pool)
+            .await
+            .ok_or(AppError::ServiceUnavailable)?;
+        set_video_thumbnail(&connection, video.id, &thumbnail_path, &thumbnail_sha256)
+    }
+    .await;
+
+    let sprite = match SpriteLayout::for_length(video.video_length) {
+        Some(layout) => {
+            let filter = format!(
+                "fps=1/{interval},scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,tile={columns}x{rows}",
+                interval = layout.interval,
+                w = SPRITE_TILE_WIDTH,
+                h = SPRITE_TILE_HEIGHT,
+                columns = layout.columns,
+                rows = layout.rows
+            );
+            run_ffmpeg(
+                video,
+                &["-i", &video.video_path, "-vf", &filter, "-frames:v", "1"],
+                &format!("{}/sprite.jpg", folder),
+            )
+            .await
+        }
+        None => Ok(()),
+    };
+
+    // Taken a little way in, where something is usually happening
+    let preview_start = if video.video_length > PREVIEW_LENGTH_SECONDS {
+        (video.video_length * 0.1).min(video.video_length - PREVIEW_LENGTH_SECONDS)
+    } else {
+        0.0
+    };
+    let preview_start = preview_start.to_string();
+    let preview_length = PREVIEW_LENGTH_SECONDS.to_string();
+    let clip_args = [
+        "-ss",
+        &preview_start,
+        "-t",
+        &preview_length,
+        "-i",
+        &video.video_path,
+        "-an",
+        "-vf",
+        "fps=12,scale=320:-2",
+    ];
+    let webp = run_ffmpeg(
+        video,
+        &[
+            &clip_args[..],
+            &["-c:v", "libwebp", "-loop", "0", "-quality", "60"],
+        ]
+        .concat(),
+        &format!("{}/preview.webp", folder),
+    )
+    .await;
+    let mp4 = run_ffmpeg(
+        video,
+        &[
+            &clip_args[..],
+            &[
+                "-c:v",
+                "libx264",
+                "-pix_fmt",
+                "yuv420p",
+                "-movflags",
+                "+faststart",
+            ],
+        ]
+        .concat(),
+        &format!("{}/preview.mp4", folder),
+    )
+    .await;
+
+    thumbnail.and(sprite).and(webp).and(mp4)
+}
+
+/// Removes everything `generate_previews` made for a video
+pub async fn remove_previews(video: &Video) {
+    let folder = preview_folder(video);
+    if std::path::Path::new(&folder).exists() {
+        if let Err(e) = fs::remove_dir_all(&folder).await {
+            warn!("Failed to remove folder {} with error: {}", folder, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpriteLayout;
+
+    #[test]
+    fn sprite_layout_needs_a_known_length() {
+        assert!(SpriteLayout::for_length(0.0).is_none());
+        assert!(SpriteLayout::for_length(-1.0).is_none());
+    }
+
+    #[test]
+    fn sprite_layout_for_short_video_uses_minimum_interval() {
+        let layout = SpriteLayout::for_length(25.0).unwrap();
+        assert_eq!(layout.interval, 2.0);
+        assert_eq!(layout.frames, 13);
+        assert_eq!(layout.columns, 10);
+        assert_eq!(layout.rows, 2);
+
+        let layout = SpriteLayout::for_length(5.0).unwrap();
+        assert_eq!((layout.frames, layout.columns, layout.rows), (3, 3, 1));
+    }
+
+    #[test]
+    fn sprite_layout_for_long_video_caps_frames() {
+        let layout = SpriteLayout::for_length(1000.0).unwrap();
+        assert_eq!(layout.interval, 10.0);
+        assert_eq!(layout.frames, 100);
+        assert_eq!(layout.columns, 10);
+        assert_eq!(layout.rows, 10);
+    }
+}
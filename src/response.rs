@@ -0,0 +1,86 @@
+use crate::{
+    collection::model::CollectionContents,
+    comment::model::CommentWithAuthor,
+    error::AppResult,
+    folder::model::{FolderContents, FolderList},
+    models::{
+        CaptionTrack, Chapter, Collection, Comment, Folder, Job, Playlist, PlaylistItem,
+        StorageHealth, User, Video,
+    },
+    playlist::model::{OneTimePlaylistLink, PlaylistDetails},
+    search::model::VideoSearchResult,
+    storage::model::ReconcileReport,
+    tag::model::TagCount,
+    video::model::{VideoDetails, VideoPage, VideoUpload},
+};
+use rocket::serde::json::Json;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// The body of every successful JSON response, with the endpoint's payload in `data`
+// Every payload needs an alias here to be referenced from the OpenAPI document
+#[derive(Debug, Serialize, ToSchema)]
+#[aliases(
+    UserResponse = ApiResponse<User>,
+    UserListResponse = ApiResponse<Vec<User>>,
+    VideoResponse = ApiResponse<Video>,
+    VideoListResponse = ApiResponse<Vec<Video>>,
+    VideoPageResponse = ApiResponse<VideoPage>,
+    VideoDetailsResponse = ApiResponse<VideoDetails>,
+    VideoUploadResponse = ApiResponse<VideoUpload>,
+    SearchResponse = ApiResponse<Vec<VideoSearchResult>>,
+    TagCountListResponse = ApiResponse<Vec<TagCount>>,
+    CaptionTrackResponse = ApiResponse<CaptionTrack>,
+    ChapterListResponse = ApiResponse<Vec<Chapter>>,
+    CommentResponse = ApiResponse<Comment>,
+    CommentListResponse = ApiResponse<Vec<CommentWithAuthor>>,
+    FolderResponse = ApiResponse<Folder>,
+    FolderListResponse = ApiResponse<FolderList>,
+    FolderContentsResponse = ApiResponse<FolderContents>,
+    CollectionResponse = ApiResponse<Collection>,
+    CollectionListResponse = ApiResponse<Vec<Collection>>,
+    CollectionContentsResponse = ApiResponse<CollectionContents>,
+    PlaylistResponse = ApiResponse<Playlist>,
+    PlaylistListResponse = ApiResponse<Vec<Playlist>>,
+    PlaylistDetailsResponse = ApiResponse<PlaylistDetails>,
+    PlaylistItemResponse = ApiResponse<PlaylistItem>,
+    OneTimePlaylistLinkResponse = ApiResponse<OneTimePlaylistLink>,
+    ReconcileReportResponse = ApiResponse<ReconcileReport>,
+    StorageHealthListResponse = ApiResponse<Vec<StorageHealth>>,
+    JobResponse = ApiResponse<Job>,
+    JobListResponse = ApiResponse<Vec<Job>>
+)]
+pub struct ApiResponse<T> {
+    pub status: u16,
+    pub message: String,
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn ok(data: T) -> Json<Self> {
+        Json(ApiResponse {
+            status: 200,
+            message: String::from("Ok"),
+            data,
+        })
+    }
+}
+
+/// The body of successful JSON responses without a payload
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiMessage {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ApiMessage {
+    pub fn ok() -> Json<Self> {
+        Json(ApiMessage {
+            status: 200,
+            message: String::from("Ok"),
+        })
+    }
+}
+
+pub type ApiResult<T> = AppResult<Json<ApiResponse<T>>>;
+pub type ApiMessageResult = AppResult<Json<ApiMessage>>;
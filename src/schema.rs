@@ -1,3 +1,115 @@
+table! {
+    caption_tracks (id) {
+        id -> Int4,
+        video_id -> Int4,
+        language -> Text,
+        label -> Text,
+        source -> Text,
+        content -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    chapters (id) {
+        id -> Int4,
+        video_id -> Int4,
+        start_seconds -> Float8,
+        title -> Text,
+    }
+}
+
+table! {
+    collection_videos (id) {
+        id -> Int4,
+        collection_id -> Int4,
+        video_id -> Int4,
+    }
+}
+
+table! {
+    collections (id) {
+        id -> Int4,
+        collection_id -> Text,
+        owner_id -> Int4,
+        name -> Text,
+        description -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    comments (id) {
+        id -> Int4,
+        video_id -> Int4,
+        author_id -> Int4,
+        timestamp_seconds -> Float8,
+        body -> Text,
+        parent_id -> Nullable<Int4>,
+        resolved -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    folder_shares (id) {
+        id -> Int4,
+        folder_id -> Int4,
+        user_id -> Int4,
+    }
+}
+
+table! {
+    folders (id) {
+        id -> Int4,
+        folder_id -> Text,
+        owner_id -> Int4,
+        parent_id -> Nullable<Int4>,
+        name -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    jobs (id) {
+        id -> Int4,
+        queue -> Text,
+        kind -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Int4,
+        max_attempts -> Int4,
+        run_at -> Timestamp,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    media_blobs (id) {
+        id -> Int4,
+        sha256 -> Text,
+        path -> Text,
+        file_size -> Int8,
+        ref_count -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    one_time_playlist (id) {
+        id -> Int4,
+        playlist_id -> Int4,
+        created_at -> Timestamp,
+        one_time_pass -> Text,
+    }
+}
+
 table! {
     one_time_video (id) {
         id -> Int4,
@@ -7,6 +119,56 @@ table! {
     }
 }
 
+table! {
+    playlist_items (id) {
+        id -> Int4,
+        playlist_id -> Int4,
+        video_id -> Int4,
+        position -> Int4,
+    }
+}
+
+table! {
+    playlist_shares (id) {
+        id -> Int4,
+        playlist_id -> Int4,
+        user_id -> Int4,
+    }
+}
+
+table! {
+    playlists (id) {
+        id -> Int4,
+        playlist_id -> Text,
+        owner_id -> Int4,
+        title -> Text,
+        description -> Text,
+        visibility -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    storage_health (id) {
+        id -> Int4,
+        kind -> Text,
+        path -> Text,
+        expected_sha256 -> Nullable<Text>,
+        actual_sha256 -> Nullable<Text>,
+        status -> Text,
+        checked_at -> Timestamp,
+    }
+}
+
+table! {
+    tags (id) {
+        id -> Int4,
+        owner_id -> Int4,
+        name -> Text,
+    }
+}
+
 table! {
     user_permissions (id) {
         id -> Int4,
@@ -21,6 +183,25 @@ table! {
         email -> Text,
         displayname -> Text,
         permissions -> Array<Int4>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        last_login_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    video_downloads (id) {
+        id -> Int4,
+        video_id -> Int4,
+        user_id -> Nullable<Int4>,
+        ip_address -> Nullable<Text>,
+        downloaded_at -> Timestamp,
+    }
+}
+
+table! {
+    video_file_size_backfill (video_id) {
+        video_id -> Int4,
     }
 }
 
@@ -32,6 +213,19 @@ table! {
     }
 }
 
+table! {
+    video_tags (video_id, tag_id) {
+        video_id -> Int4,
+        tag_id -> Int4,
+    }
+}
+
+table! {
+    video_timestamp_backfill (video_id) {
+        video_id -> Int4,
+    }
+}
+
 table! {
     videos (id) {
         id -> Int4,
@@ -43,18 +237,74 @@ table! {
         video_desc -> Text,
         owner_id -> Int4,
         thumbnail_path -> Nullable<Text>,
+        visibility -> Text,
+        password_hash -> Nullable<Text>,
+        file_size -> Int8,
+        status -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        folder_id -> Nullable<Int4>,
+        source_video_id -> Nullable<Int4>,
+        original_filename -> Text,
+        downloads_enabled -> Bool,
+        deleted_at -> Nullable<Timestamp>,
+        sha256 -> Nullable<Text>,
+        thumbnail_sha256 -> Nullable<Text>,
     }
 }
 
+joinable!(caption_tracks -> videos (video_id));
+joinable!(chapters -> videos (video_id));
+joinable!(collection_videos -> collections (collection_id));
+joinable!(collection_videos -> videos (video_id));
+joinable!(collections -> users (owner_id));
+joinable!(comments -> users (author_id));
+joinable!(comments -> videos (video_id));
+joinable!(folder_shares -> folders (folder_id));
+joinable!(folder_shares -> users (user_id));
+joinable!(folders -> users (owner_id));
+joinable!(one_time_playlist -> playlists (playlist_id));
 joinable!(one_time_video -> videos (video_id));
+joinable!(playlist_items -> playlists (playlist_id));
+joinable!(playlist_items -> videos (video_id));
+joinable!(playlist_shares -> playlists (playlist_id));
+joinable!(playlist_shares -> users (user_id));
+joinable!(playlists -> users (owner_id));
+joinable!(tags -> users (owner_id));
+joinable!(video_downloads -> users (user_id));
+joinable!(video_downloads -> videos (video_id));
+joinable!(video_file_size_backfill -> videos (video_id));
 joinable!(video_shares -> users (user_id));
 joinable!(video_shares -> videos (video_id));
+joinable!(video_tags -> tags (tag_id));
+joinable!(video_tags -> videos (video_id));
+joinable!(video_timestamp_backfill -> videos (video_id));
+joinable!(videos -> folders (folder_id));
 joinable!(videos -> users (owner_id));
 
 allow_tables_to_appear_in_same_query!(
+    caption_tracks,
+    chapters,
+    collection_videos,
+    collections,
+    comments,
+    folder_shares,
+    folders,
+    jobs,
+    media_blobs,
+    one_time_playlist,
     one_time_video,
+    playlist_items,
+    playlist_shares,
+    playlists,
+    storage_health,
+    tags,
     user_permissions,
     users,
+    video_downloads,
+    video_file_size_backfill,
     video_shares,
+    video_tags,
+    video_timestamp_backfill,
     videos,
 );
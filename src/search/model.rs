@@ -0,0 +1,20 @@
+use crate::models::Video;
+use diesel::sql_types::{Float4, Text};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(QueryableByName, Debug, Serialize, ToSchema)]
+pub struct VideoSearchResult {
+    #[diesel(embed)]
+    #[serde(flatten)]
+    pub video: Video,
+    #[sql_type = "Float4"]
+    pub rank: f32,
+    /// The HTML escaped video name with matching terms wrapped in `<mark>` tags
+    #[sql_type = "Text"]
+    pub name_highlight: String,
+    /// HTML escaped fragments of the description around the matching terms,
+    /// which are wrapped in `<mark>` tags
+    #[sql_type = "Text"]
+    pub snippet: String,
+}
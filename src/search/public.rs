@@ -0,0 +1,49 @@
+use crate::{
+    auth::util::get_user_from_cookies,
+    db::{get_connection, DbPool},
+    error::AppError,
+    response::{ApiResponse, ApiResult},
+    search::{model::VideoSearchResult, sql::search_videos},
+};
+use rocket::http::CookieJar;
+use rocket::State;
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 50;
+
+#[utoipa::path(
+    context_path = "/api",
+    tag = "search",
+    responses((status = 200, body = SearchResponse))
+)]
+#[get("/search?<q>&<limit>&<offset>")]
+pub async fn search(
+    q: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<VideoSearchResult>> {
+    let q = q.trim();
+    if q.is_empty() || q.len() > 256 {
+        info!("Invalid search query {}", q);
+        return Err(AppError::BadRequest);
+    }
+
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let results = search_videos(
+        &connection,
+        q,
+        user.map(|user| user.id),
+        limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT),
+        offset.unwrap_or(0).max(0),
+    )?;
+
+    Ok(ApiResponse::ok(results))
+}
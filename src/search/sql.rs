@@ -0,0 +1,61 @@
+use crate::{
+    error::AppResult,
+    search::{model::VideoSearchResult, util::highlight_html},
+};
+use diesel::{
+    prelude::*,
+    sql_types::{BigInt, Integer, Nullable, Text},
+};
+
+// `videos.search_vector` is maintained by a trigger (see the video_search migration)
+// and is left out of `schema.rs` since Diesel has no `tsvector` type, so these
+// queries are written in SQL.
+// The headlines mark matches with chr(2) and chr(3) instead of tags, see `highlight_html`.
+const SEARCH_VIDEOS_QUERY: &str = "
+    SELECT videos.*,
+        ts_rank(videos.search_vector, query) AS rank,
+        ts_headline('english', videos.video_name, query,
+            'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', HighlightAll=true')
+            AS name_highlight,
+        ts_headline('english', videos.video_desc, query,
+            'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', MaxFragments=2') AS snippet
+    FROM videos, websearch_to_tsquery('english', $1) query
+    WHERE videos.search_vector @@ query
+        AND videos.deleted_at IS NULL
+        AND (
+            videos.owner_id = $2
+            OR video_shared_with(videos.id, $2)
+            OR (
+                videos.password_hash IS NULL
+                AND (
+                    videos.visibility = 'public'
+                    OR (videos.visibility = 'internal' AND $2 IS NOT NULL)
+                )
+            )
+        )
+    ORDER BY rank DESC, videos.id DESC
+    LIMIT $3 OFFSET $4";
+
+/// Searches the videos visible to `user_id` (None for anonymous users).
+/// Unlisted and password protected videos only show up for their owner and shares.
+pub fn search_videos(
+    connection: &PgConnection,
+    query: &str,
+    user_id: Option<i32>,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<VideoSearchResult>> {
+    Ok(diesel::sql_query(SEARCH_VIDEOS_QUERY)
+        .bind::<Text, _>(query)
+        .bind::<Nullable<Integer>, _>(user_id)
+        .bind::<BigInt, _>(limit)
+        .bind::<BigInt, _>(offset)
+        .load::<VideoSearchResult>(connection)?
+        .into_iter()
+        .map(|result| VideoSearchResult {
+            name_highlight: highlight_html(&result.name_highlight),
+            snippet: highlight_html(&result.snippet),
+            ..result
+        })
+        .collect())
+}
@@ -0,0 +1,38 @@
+/// Put around matching terms by `ts_headline` in place of `<mark>` tags, so the
+/// stored text can be escaped before any markup is added to it
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_STOP: char = '\u{3}';
+
+/// Escapes a `ts_headline` result for use as HTML and turns its highlight markers
+/// into `<mark>` tags. Names and descriptions are stored as typed by their owner.
+pub fn highlight_html(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            HIGHLIGHT_START => html.push_str("<mark>"),
+            HIGHLIGHT_STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::highlight_html;
+
+    #[test]
+    fn highlight_html_escapes_stored_markup() {
+        let description = "\u{2}cats\u{3} <script>alert(\"x\")</script> & <b>dogs</b>";
+        assert_eq!(
+            highlight_html(description),
+            "<mark>cats</mark> &lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt; &amp; \
+             &lt;b&gt;dogs&lt;/b&gt;"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// The parts of a video storage reconciliation compares with the files on disk
+#[derive(Debug, Queryable)]
+pub struct VideoFile {
+    pub id: i32,
+    pub owner_id: i32,
+    pub video_id: String,
+    pub video_path: String,
+    pub status: String,
+    pub updated_at: NaiveDateTime,
+}
+
+/// What a storage reconciliation found, and whether it was repaired
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ReconcileReport {
+    /// Files under `videos/` no video points to, removed when repairing
+    pub orphaned_files: Vec<String>,
+    /// Ids of ready videos whose file is gone, marked as failed when repairing
+    pub missing_files: Vec<String>,
+    /// Upload and clip files abandoned while being written, removed when repairing
+    pub stale_temp_files: Vec<String>,
+    /// Ids of videos that have been processing for too long, the ones without a
+    /// file are marked as failed when repairing
+    pub stuck_videos: Vec<String>,
+    pub repaired: bool,
+}
+
+/// What a file checked by the scrub is, stored as text in `storage_health.kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoredFileKind {
+    Media,
+    Thumbnail,
+}
+
+impl StoredFileKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StoredFileKind::Media => "media",
+            StoredFileKind::Thumbnail => "thumbnail",
+        }
+    }
+}
+
+/// Stored as text in `storage_health.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileHealth {
+    Ok,
+    Missing,
+    /// The file's content no longer has the hash recorded for it
+    Corrupt,
+}
+
+impl FileHealth {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileHealth::Ok => "ok",
+            FileHealth::Missing => "missing",
+            FileHealth::Corrupt => "corrupt",
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    auth::util::{get_user_from_cookies, user_has_admin_permission},
+    db::{get_connection, DbPool},
+    error::AppError,
+    models::StorageHealth,
+    response::{ApiResponse, ApiResult},
+    storage::{
+        model::ReconcileReport, sql::get_storage_health, util::reconcile_storage_in_background,
+    },
+};
+use rocket::http::CookieJar;
+use rocket::State;
+
+/// Finds video files without a video and videos without a file.
+/// Only reports them unless `repair` is set.
+#[utoipa::path(
+    context_path = "/api/storage",
+    tag = "admin",
+    responses((status = 200, body = ReconcileReportResponse))
+)]
+#[post("/reconcile?<repair>")]
+pub async fn reconcile(
+    repair: Option<bool>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<ReconcileReport> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    if !user_has_admin_permission(&user) {
+        info!("User {} can not reconcile storage", user.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let report = reconcile_storage_in_background(pool, repair.unwrap_or(false)).await?;
+
+    Ok(ApiResponse::ok(report))
+}
+
+/// Lists the results of the last storage scrub, only the missing and corrupt files
+/// unless `all` is set
+#[utoipa::path(
+    context_path = "/api/storage",
+    tag = "admin",
+    responses((status = 200, body = StorageHealthListResponse))
+)]
+#[get("/health?<all>")]
+pub async fn storage_health(
+    all: Option<bool>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<StorageHealth>> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    if !user_has_admin_permission(&user) {
+        info!("User {} can not see storage health", user.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let health = get_storage_health(&connection, all.unwrap_or(false))?;
+
+    Ok(ApiResponse::ok(health))
+}
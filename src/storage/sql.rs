@@ -0,0 +1,85 @@
+extern crate diesel;
+
+use crate::{
+    error::AppResult,
+    models::*,
+    storage::model::{FileHealth, VideoFile},
+};
+use diesel::{dsl::now, prelude::*};
+
+pub fn get_video_files(connection: &PgConnection) -> AppResult<Vec<VideoFile>> {
+    use crate::schema::videos::dsl;
+
+    Ok(crate::schema::videos::table
+        .select((
+            dsl::id,
+            dsl::owner_id,
+            dsl::video_id,
+            dsl::video_path,
+            dsl::status,
+            dsl::updated_at,
+        ))
+        .load::<VideoFile>(connection)?)
+}
+
+/// The path and hash of every media blob
+pub fn get_media_blob_hashes(connection: &PgConnection) -> AppResult<Vec<(String, String)>> {
+    use crate::schema::media_blobs::dsl;
+
+    Ok(crate::schema::media_blobs::table
+        .select((dsl::path, dsl::sha256))
+        .order(dsl::id.asc())
+        .load::<(String, String)>(connection)?)
+}
+
+/// The path and hash of every thumbnail that was hashed when it was generated
+pub fn get_thumbnail_hashes(connection: &PgConnection) -> AppResult<Vec<(String, String)>> {
+    use crate::schema::videos::dsl;
+
+    Ok(crate::schema::videos::table
+        .filter(dsl::thumbnail_path.is_not_null())
+        .filter(dsl::thumbnail_sha256.is_not_null())
+        .select((dsl::thumbnail_path, dsl::thumbnail_sha256))
+        .order(dsl::id.asc())
+        .load::<(Option<String>, Option<String>)>(connection)?
+        .into_iter()
+        .filter_map(|(path, sha256)| Some((path?, sha256?)))
+        .collect())
+}
+
+pub fn record_storage_health(
+    connection: &PgConnection,
+    health: &StorageHealthNoId,
+) -> AppResult<()> {
+    use crate::schema::storage_health::dsl;
+
+    diesel::insert_into(crate::schema::storage_health::table)
+        .values(health)
+        .on_conflict(dsl::path)
+        .do_update()
+        .set((health, dsl::checked_at.eq(now)))
+        .execute(connection)?;
+    Ok(())
+}
+
+/// Forgets the results for files that are no longer stored
+pub fn delete_storage_health_except(connection: &PgConnection, paths: &[String]) -> AppResult<()> {
+    use crate::schema::storage_health::dsl;
+
+    diesel::delete(crate::schema::storage_health::table.filter(dsl::path.ne_all(paths)))
+        .execute(connection)?;
+    Ok(())
+}
+
+/// Gets the results of the last scrub, only the problems unless `all` is set
+pub fn get_storage_health(connection: &PgConnection, all: bool) -> AppResult<Vec<StorageHealth>> {
+    use crate::schema::storage_health::dsl;
+
+    let mut query = crate::schema::storage_health::table.into_boxed();
+    if !all {
+        query = query.filter(dsl::status.ne(FileHealth::Ok.as_str()));
+    }
+    Ok(query
+        .order((dsl::checked_at.desc(), dsl::id.desc()))
+        .load::<StorageHealth>(connection)?)
+}
@@ -0,0 +1,304 @@
+use crate::{
+    db::{env_or, get_connection, DbPool},
+    error::{AppError, AppResult},
+    job::{model::JobKind, util::schedule_job},
+    media::util::{hash_file, MEDIA_BLOB_FOLDER},
+    models::StorageHealthNoId,
+    storage::{
+        model::{FileHealth, ReconcileReport, StoredFileKind},
+        sql::{
+            delete_storage_health_except, get_media_blob_hashes, get_thumbnail_hashes,
+            get_video_files, record_storage_health,
+        },
+    },
+    video::{
+        model::VideoStatus,
+        sql::{get_unhashed_videos, set_video_status},
+        util::TEMP_FILE_MARKERS,
+    },
+};
+use diesel::PgConnection;
+use std::{
+    collections::HashSet,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const VIDEO_FOLDER: &str = "videos";
+/// Uploads and clips can take a while, so their files and videos are only considered
+/// abandoned once this old
+const STALE_TEMP_FILE_SECONDS: u64 = 24 * 60 * 60;
+const DEFAULT_RECONCILE_INTERVAL_SECONDS: u64 = 24 * 60 * 60;
+const DEFAULT_SCRUB_INTERVAL_SECONDS: u64 = 7 * 24 * 60 * 60;
+const DEFAULT_SCRUB_BYTES_PER_SECOND: u64 = 16 * 1024 * 1024;
+
+fn is_temp_file(name: &str) -> bool {
+    TEMP_FILE_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+fn is_stale(path: &Path) -> bool {
+    let modified = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(e) => {
+            warn!("Failed to read age of {} with error: {}", path.display(), e);
+            return false;
+        }
+    };
+    match SystemTime::now().duration_since(modified) {
+        Ok(age) => age >= Duration::from_secs(STALE_TEMP_FILE_SECONDS),
+        Err(_) => false,
+    }
+}
+
+/// Every file in the folders under `videos/`, one per user plus the media blobs
+fn list_video_files() -> AppResult<Vec<String>> {
+    let mut files = Vec::new();
+    if !Path::new(VIDEO_FOLDER).exists() {
+        return Ok(files);
+    }
+    for user_folder in fs::read_dir(VIDEO_FOLDER)? {
+        let user_folder = user_folder?;
+        if !user_folder.file_type()?.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(user_folder.path())? {
+            let file = file?;
+            if !file.file_type()?.is_file() {
+                continue;
+            }
+            files.push(format!(
+                "{}/{}/{}",
+                VIDEO_FOLDER,
+                user_folder.file_name().to_string_lossy(),
+                file.file_name().to_string_lossy()
+            ));
+        }
+    }
+    Ok(files)
+}
+
+fn remove_file(path: &str) {
+    if let Err(e) = fs::remove_file(path) {
+        warn!("Failed to remove file {} with error: {}", path, e);
+    }
+}
+
+/// Compares the files under `videos/` with the videos table, finding files no video
+/// points to, ready videos without a file and videos stuck processing.
+///
+/// The folder is listed before the videos are loaded, so a file that is moved into
+/// place during the scan always has its video. Recent media blobs are left alone,
+/// a clip is moved into place before its video points to it. With `repair`, orphaned and stale temporary files are removed and
+/// videos without a file are marked as failed, otherwise everything is only reported.
+pub fn reconcile_storage(connection: &PgConnection, repair: bool) -> AppResult<ReconcileReport> {
+    let mut report = ReconcileReport {
+        repaired: repair,
+        ..ReconcileReport::default()
+    };
+
+    let files = list_video_files()?;
+    let videos = get_video_files(connection)?;
+    let video_paths = videos
+        .iter()
+        .map(|video| video.video_path.as_str())
+        .collect::<HashSet<&str>>();
+    let processing = VideoStatus::Processing.as_str();
+
+    for path in files {
+        if video_paths.contains(path.as_str()) {
+            continue;
+        }
+        if is_temp_file(&path) {
+            if is_stale(Path::new(&path)) {
+                warn!("Found abandoned temporary file {}", path);
+                if repair {
+                    remove_file(&path);
+                }
+                report.stale_temp_files.push(path);
+            }
+            continue;
+        }
+        if path.starts_with(MEDIA_BLOB_FOLDER) && !is_stale(Path::new(&path)) {
+            continue;
+        }
+        warn!("Found file {} without a video", path);
+        if repair {
+            remove_file(&path);
+        }
+        report.orphaned_files.push(path);
+    }
+
+    let stuck_before =
+        chrono::Utc::now().naive_utc() - chrono::Duration::seconds(STALE_TEMP_FILE_SECONDS as i64);
+    for video in &videos {
+        let has_file = Path::new(&video.video_path).exists();
+        if video.status == processing {
+            // Uploads that crashed between their insert and moving the file into place
+            if video.updated_at < stuck_before {
+                warn!(
+                    "Video {} has been processing since {}",
+                    video.video_id, video.updated_at
+                );
+                if repair && !has_file {
+                    set_video_status(connection, video.id, VideoStatus::Failed)?;
+                }
+                report.stuck_videos.push(video.video_id.clone());
+            }
+            continue;
+        }
+        if video.status != VideoStatus::Ready.as_str() || has_file {
+            continue;
+        }
+        warn!(
+            "Video {} has no file at {}",
+            video.video_id, video.video_path
+        );
+        if repair {
+            set_video_status(connection, video.id, VideoStatus::Failed)?;
+        }
+        report.missing_files.push(video.video_id.clone());
+    }
+
+    Ok(report)
+}
+
+/// Runs `reconcile_storage` on a blocking thread, it walks the whole video folder
+pub async fn reconcile_storage_in_background(
+    pool: &DbPool,
+    repair: bool,
+) -> AppResult<ReconcileReport> {
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    match rocket::tokio::task::spawn_blocking(move || reconcile_storage(&connection, repair)).await
+    {
+        Ok(report) => report,
+        Err(e) => Err(AppError::Internal(format!(
+            "storage reconciliation panicked ({})",
+            e
+        ))),
+    }
+}
+
+/// Queues a reconciliation every `STORAGE_RECONCILE_INTERVAL_SECONDS`, starting at
+/// launch. Problems are only logged unless `STORAGE_RECONCILE_REPAIR` is `true`.
+pub async fn schedule_reconcile_job(pool: DbPool) {
+    let period = Duration::from_secs(env_or(
+        "STORAGE_RECONCILE_INTERVAL_SECONDS",
+        DEFAULT_RECONCILE_INTERVAL_SECONDS,
+    ));
+    let repair = env_or("STORAGE_RECONCILE_REPAIR", false);
+    schedule_job(pool, JobKind::ReconcileStorage { repair }, period).await
+}
+
+/// Re-hashes a stored file and compares it with the hash recorded for it
+async fn check_file(
+    path: &str,
+    expected_sha256: &str,
+    max_bytes_per_second: u64,
+) -> (FileHealth, Option<String>) {
+    match hash_file(path, Some(max_bytes_per_second)).await {
+        Ok(sha256) if sha256 == expected_sha256 => (FileHealth::Ok, Some(sha256)),
+        Ok(sha256) => {
+            warn!(
+                "File {} is corrupt, its hash is {} instead of {}",
+                path, sha256, expected_sha256
+            );
+            (FileHealth::Corrupt, Some(sha256))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            warn!("File {} is missing", path);
+            (FileHealth::Missing, None)
+        }
+        Err(e) => {
+            warn!("Failed to hash {} with error: {}", path, e);
+            (FileHealth::Corrupt, None)
+        }
+    }
+}
+
+/// Re-hashes every media blob and thumbnail, recording the outcome for each in
+/// `storage_health`. Files of videos that were not hashed yet have nothing to be
+/// compared with, they are only recorded when missing and re-hashed once
+/// `backfill_video_hashes` made blobs of them. Files are read at most
+/// `max_bytes_per_second` (0 for no limit) and no connection is held while hashing,
+/// so this can run next to regular traffic.
+pub async fn scrub_storage(pool: &DbPool, max_bytes_per_second: u64) -> AppResult<()> {
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    let files = get_media_blob_hashes(&connection)?
+        .into_iter()
+        .map(|(path, sha256)| (StoredFileKind::Media, path, Some(sha256)))
+        .chain(
+            get_thumbnail_hashes(&connection)?
+                .into_iter()
+                .map(|(path, sha256)| (StoredFileKind::Thumbnail, path, Some(sha256))),
+        )
+        .chain(
+            get_unhashed_videos(&connection)?
+                .into_iter()
+                .map(|(_, path)| (StoredFileKind::Media, path, None)),
+        )
+        .collect::<Vec<(StoredFileKind, String, Option<String>)>>();
+    drop(connection);
+
+    let mut problems = 0;
+    let mut checked = Vec::with_capacity(files.len());
+    for (kind, path, expected_sha256) in files {
+        let (health, actual_sha256) = match &expected_sha256 {
+            Some(expected_sha256) => check_file(&path, expected_sha256, max_bytes_per_second).await,
+            None if Path::new(&path).exists() => continue,
+            None => {
+                warn!("File {} is missing", path);
+                (FileHealth::Missing, None)
+            }
+        };
+        if health != FileHealth::Ok {
+            problems += 1;
+        }
+        let connection = get_connection(pool)
+            .await
+            .ok_or(AppError::ServiceUnavailable)?;
+        record_storage_health(
+            &connection,
+            &StorageHealthNoId {
+                kind: kind.as_str().to_string(),
+                path: path.clone(),
+                expected_sha256,
+                actual_sha256,
+                status: health.as_str().to_string(),
+            },
+        )?;
+        checked.push(path);
+    }
+
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    delete_storage_health_except(&connection, &checked)?;
+    info!("Scrubbed storage, found {} damaged files", problems);
+    Ok(())
+}
+
+/// Queues a scrub every `STORAGE_SCRUB_INTERVAL_SECONDS`, starting at launch and
+/// reading at most `STORAGE_SCRUB_BYTES_PER_SECOND`
+pub async fn schedule_scrub_job(pool: DbPool) {
+    let period = Duration::from_secs(env_or(
+        "STORAGE_SCRUB_INTERVAL_SECONDS",
+        DEFAULT_SCRUB_INTERVAL_SECONDS,
+    ));
+    let max_bytes_per_second = env_or(
+        "STORAGE_SCRUB_BYTES_PER_SECOND",
+        DEFAULT_SCRUB_BYTES_PER_SECOND,
+    );
+    schedule_job(
+        pool,
+        JobKind::ScrubStorage {
+            max_bytes_per_second,
+        },
+        period,
+    )
+    .await
+}
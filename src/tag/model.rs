@@ -0,0 +1,12 @@
+use diesel::sql_types::{BigInt, Text};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(QueryableByName, Debug, Serialize, ToSchema)]
+pub struct TagCount {
+    #[sql_type = "Text"]
+    pub name: String,
+    /// Number of videos with this tag
+    #[sql_type = "BigInt"]
+    pub count: i64,
+}
@@ -0,0 +1,47 @@
+use crate::{
+    auth::util::get_user_from_cookies,
+    db::{get_connection, DbPool},
+    error::AppError,
+    response::{ApiResponse, ApiResult},
+    tag::{model::TagCount, sql::get_tag_counts, util::normalize_tag},
+};
+use rocket::http::CookieJar;
+use rocket::State;
+
+const DEFAULT_TAG_LIMIT: i64 = 20;
+const MAX_TAG_LIMIT: i64 = 100;
+
+/// Lists the caller's tags with how many videos use them, for autocompletion
+#[utoipa::path(
+    context_path = "/api",
+    tag = "tag",
+    responses((status = 200, body = TagCountListResponse))
+)]
+#[get("/tags?<prefix>&<limit>")]
+pub async fn get_tags(
+    prefix: Option<String>,
+    limit: Option<i64>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Vec<TagCount>> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let prefix = prefix
+        .as_deref()
+        .and_then(normalize_tag)
+        .unwrap_or_default();
+
+    let counts = get_tag_counts(
+        &connection,
+        user.id,
+        &prefix,
+        limit.unwrap_or(DEFAULT_TAG_LIMIT).clamp(1, MAX_TAG_LIMIT),
+    )?;
+
+    Ok(ApiResponse::ok(counts))
+}
@@ -0,0 +1,90 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*, tag::model::TagCount};
+use diesel::{
+    prelude::*,
+    sql_types::{BigInt, Integer, Text},
+};
+
+pub fn get_tags_for_video(connection: &PgConnection, video_id: i32) -> AppResult<Vec<String>> {
+    use crate::schema::{tags, video_tags};
+
+    Ok(tags::table
+        .inner_join(video_tags::table)
+        .filter(video_tags::dsl::video_id.eq(video_id))
+        .select(tags::dsl::name)
+        .order(tags::dsl::name.asc())
+        .load::<String>(connection)?)
+}
+
+/// Replaces the tags of a video. Tags are created in the owner's namespace as needed.
+pub fn set_video_tags(
+    connection: &PgConnection,
+    video_id: i32,
+    owner_id: i32,
+    names: &[String],
+) -> AppResult<()> {
+    use crate::schema::{tags, video_tags};
+
+    connection.transaction::<_, diesel::result::Error, _>(|| {
+        let new_tags = names
+            .iter()
+            .map(|name| TagNoId {
+                owner_id,
+                name: name.clone(),
+            })
+            .collect::<Vec<TagNoId>>();
+        diesel::insert_into(tags::table)
+            .values(&new_tags)
+            .on_conflict_do_nothing()
+            .execute(connection)?;
+
+        let tag_ids = tags::table
+            .filter(tags::dsl::owner_id.eq(owner_id))
+            .filter(tags::dsl::name.eq_any(names))
+            .select(tags::dsl::id)
+            .load::<i32>(connection)?;
+
+        diesel::delete(video_tags::table.filter(video_tags::dsl::video_id.eq(video_id)))
+            .execute(connection)?;
+        diesel::insert_into(video_tags::table)
+            .values(
+                &tag_ids
+                    .into_iter()
+                    .map(|tag_id| VideoTag { video_id, tag_id })
+                    .collect::<Vec<VideoTag>>(),
+            )
+            .execute(connection)
+    })?;
+    Ok(())
+}
+
+/// Gets a user's tags that are in use, most used first, optionally starting with `prefix`
+pub fn get_tag_counts(
+    connection: &PgConnection,
+    owner_id: i32,
+    prefix: &str,
+    limit: i64,
+) -> AppResult<Vec<TagCount>> {
+    let pattern = format!(
+        "{}%",
+        prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    Ok(diesel::sql_query(
+        "SELECT tags.name, COUNT(video_tags.video_id) AS count
+        FROM tags
+            JOIN video_tags ON video_tags.tag_id = tags.id
+            JOIN videos ON videos.id = video_tags.video_id
+        WHERE tags.owner_id = $1 AND tags.name LIKE $2 AND videos.deleted_at IS NULL
+        GROUP BY tags.name
+        ORDER BY count DESC, tags.name ASC
+        LIMIT $3",
+    )
+    .bind::<Integer, _>(owner_id)
+    .bind::<Text, _>(pattern)
+    .bind::<BigInt, _>(limit)
+    .load::<TagCount>(connection)?)
+}
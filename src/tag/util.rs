@@ -0,0 +1,34 @@
+const MAX_TAG_LENGTH: usize = 64;
+const MAX_TAGS_PER_VIDEO: usize = 32;
+
+/// Normalizes a single tag name, returning None if nothing is left of it
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let mut tag = tag.trim().to_lowercase().replace(',', "");
+    if tag.is_empty() {
+        return None;
+    }
+    if tag.len() > MAX_TAG_LENGTH {
+        info!("Tag too long. Cutting off at 64 characters");
+        let mut end = MAX_TAG_LENGTH;
+        while !tag.is_char_boundary(end) {
+            end -= 1;
+        }
+        tag.truncate(end);
+    }
+    Some(tag)
+}
+
+/// Normalizes and deduplicates tag names, keeping at most `MAX_TAGS_PER_VIDEO`
+pub fn normalize_tags<T: AsRef<str>>(tags: &[T]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().filter_map(|tag| normalize_tag(tag.as_ref())) {
+        if normalized.len() >= MAX_TAGS_PER_VIDEO {
+            info!("Too many tags. Only keeping the first 32");
+            break;
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
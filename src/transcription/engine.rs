@@ -0,0 +1,189 @@
+use crate::caption::util::normalize_language;
+use rocket::tokio::fs;
+use std::{env, path::Path};
+use tokio::process::Command;
+
+const DEFAULT_WHISPER_BINARY: &str = "whisper-cli";
+
+/// A finished transcript, as the WebVTT file written by the engine
+#[derive(Debug)]
+pub struct Transcript {
+    /// The spoken language, `und` if it could not be determined
+    pub language: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum TranscriptionEngine {
+    /// whisper.cpp, run as a separate process
+    Whisper {
+        binary: String,
+        model: String,
+        language: String,
+    },
+    /// Produces a fixed transcript without running anything, for testing setups
+    Mock,
+}
+
+impl TranscriptionEngine {
+    /// Reads the engine from the environment, returning None when transcription is not configured
+    pub fn from_env() -> Option<Self> {
+        match env::var("TRANSCRIPTION_ENGINE").ok()?.as_str() {
+            "whisper" => {
+                let model = match env::var("WHISPER_MODEL") {
+                    Ok(model) => model,
+                    Err(_) => {
+                        warn!("TRANSCRIPTION_ENGINE is whisper but WHISPER_MODEL is not set");
+                        return None;
+                    }
+                };
+                Some(TranscriptionEngine::Whisper {
+                    binary: env::var("WHISPER_BINARY")
+                        .unwrap_or_else(|_| DEFAULT_WHISPER_BINARY.to_string()),
+                    model,
+                    language: env::var("TRANSCRIPTION_LANGUAGE")
+                        .unwrap_or_else(|_| String::from("auto")),
+                })
+            }
+            "mock" => Some(TranscriptionEngine::Mock),
+            engine => {
+                warn!("Unknown TRANSCRIPTION_ENGINE {}", engine);
+                None
+            }
+        }
+    }
+
+    /// Transcribes the audio of the video at `video_path`
+    pub async fn transcribe(&self, video_path: &str, video_id: &str) -> Option<Transcript> {
+        match self {
+            TranscriptionEngine::Whisper {
+                binary,
+                model,
+                language,
+            } => transcribe_with_whisper(binary, model, language, video_path, video_id).await,
+            TranscriptionEngine::Mock => Some(Transcript {
+                language: String::from("en"),
+                content: format!(
+                    "WEBVTT\n\n00:00:00.000 --> 00:00:05.000\nMock transcript of video {}\n",
+                    video_id
+                ),
+            }),
+        }
+    }
+}
+
+async fn transcribe_with_whisper(
+    binary: &str,
+    model: &str,
+    language: &str,
+    video_path: &str,
+    video_id: &str,
+) -> Option<Transcript> {
+    let base_path = env::temp_dir().join(format!("vidmeste-transcript-{}", video_id));
+    let wav_path = base_path.with_extension("wav");
+    let vtt_path = base_path.with_extension("vtt");
+
+    let transcript = run_whisper(
+        binary, model, language, video_path, &base_path, &wav_path, &vtt_path,
+    )
+    .await;
+
+    for path in [&wav_path, &vtt_path] {
+        if path.exists() {
+            if let Err(e) = fs::remove_file(path).await {
+                warn!(
+                    "Failed to remove temporary file {} with error: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    transcript
+}
+
+async fn run_whisper(
+    binary: &str,
+    model: &str,
+    language: &str,
+    video_path: &str,
+    base_path: &Path,
+    wav_path: &Path,
+    vtt_path: &Path,
+) -> Option<Transcript> {
+    // whisper.cpp only reads 16 kHz mono WAV files
+    let output = match Command::new("ffmpeg")
+        .args(["-v", "error", "-y", "-i", video_path])
+        .args(["-vn", "-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le"])
+        .arg(wav_path)
+        .kill_on_drop(true)
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Failed to run ffmpeg with error: {}", e);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        warn!(
+            "ffmpeg failed to extract audio from {}: {}",
+            video_path,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return None;
+    }
+
+    let output = match Command::new(binary)
+        .args(["-m", model, "-l", language, "-ovtt", "-of"])
+        .arg(base_path)
+        .arg("-f")
+        .arg(wav_path)
+        .kill_on_drop(true)
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Failed to run {} with error: {}", binary, e);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        warn!(
+            "{} failed to transcribe {}: {}",
+            binary,
+            video_path,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return None;
+    }
+
+    let content = match fs::read_to_string(vtt_path).await {
+        Ok(content) => content,
+        Err(e) => {
+            warn!(
+                "Failed to read transcript {} with error: {}",
+                vtt_path.display(),
+                e
+            );
+            return None;
+        }
+    };
+
+    // whisper.cpp logs "auto-detected language: en (p = 0.97)" when detecting the language
+    let language = if language == "auto" {
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find_map(|line| line.split_once("auto-detected language:"))
+            .and_then(|(_, detected)| detected.split_whitespace().next().map(str::to_string))
+    } else {
+        Some(language.to_string())
+    }
+    .and_then(|language| normalize_language(&language))
+    .unwrap_or_else(|| String::from("und"));
+
+    Some(Transcript { language, content })
+}
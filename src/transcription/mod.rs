@@ -0,0 +1,11 @@
+//! Optional speech-to-text transcripts for uploaded videos, enabled with the
+//! `transcription` cargo feature. Media never leaves the server: the engine is a
+//! local binary configured with these environment variables:
+//!
+//! - `TRANSCRIPTION_ENGINE`: `whisper` or `mock`. Transcription is off when unset.
+//! - `WHISPER_BINARY`: path to the whisper.cpp executable (defaults to `whisper-cli`)
+//! - `WHISPER_MODEL`: path to the ggml model file, required for `whisper`
+//! - `TRANSCRIPTION_LANGUAGE`: spoken language, `auto` (the default) detects it
+
+pub mod engine;
+pub mod util;
@@ -0,0 +1,93 @@
+use crate::{
+    caption::{
+        model::{CaptionFormat, CaptionSource},
+        sql::{get_caption_track, upsert_caption_track},
+        util::caption_file_to_vtt,
+    },
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    models::{CaptionTrackNoId, Video},
+    transcription::engine::{Transcript, TranscriptionEngine},
+};
+
+/// Turns a transcript into the caption track stored for a video, returning None
+/// if the engine did not write a valid WebVTT file
+pub fn transcript_caption_track(video_id: i32, transcript: Transcript) -> Option<CaptionTrackNoId> {
+    let content = caption_file_to_vtt(&transcript.content, CaptionFormat::Vtt)?;
+    Some(CaptionTrackNoId {
+        video_id,
+        label: format!("{} (auto-generated)", transcript.language),
+        language: transcript.language,
+        source: CaptionSource::Transcript.as_str().to_string(),
+        content,
+    })
+}
+
+/// Transcribes a video with the configured engine and stores the transcript as a
+/// caption track, which also makes it searchable. Does nothing when transcription
+/// is not configured, and never replaces captions the owner uploaded.
+pub async fn transcribe_video(pool: &DbPool, video: &Video) -> AppResult<()> {
+    let engine = match TranscriptionEngine::from_env() {
+        Some(engine) => engine,
+        None => return Ok(()),
+    };
+
+    info!("Transcribing video {}", video.video_id);
+    let track = engine
+        .transcribe(&video.video_path, &video.video_id)
+        .await
+        .and_then(|transcript| transcript_caption_track(video.id, transcript))
+        .ok_or_else(|| {
+            AppError::Internal(format!("failed to transcribe video {}", video.video_id))
+        })?;
+
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    match get_caption_track(&connection, video.id, &track.language) {
+        Ok(existing) if existing.source != CaptionSource::Transcript.as_str() => {
+            info!(
+                "Video {} already has {} captions, not storing the transcript",
+                video.video_id, track.language
+            );
+            return Ok(());
+        }
+        Ok(_) | Err(AppError::NotFound) => {}
+        Err(e) => return Err(e),
+    }
+
+    upsert_caption_track(&connection, &track)?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "transcription"))]
+mod tests {
+    use super::transcript_caption_track;
+    use crate::{
+        caption::{
+            model::{CaptionFormat, CaptionSource},
+            util::caption_file_to_vtt,
+        },
+        transcription::engine::TranscriptionEngine,
+    };
+
+    #[rocket::async_test]
+    async fn mock_transcript_is_stored_as_vtt() {
+        let transcript = TranscriptionEngine::Mock
+            .transcribe("videos/1/abcdef.mp4", "abcdef")
+            .await
+            .expect("the mock engine always transcribes");
+        let track = transcript_caption_track(1, transcript).expect("the mock writes WebVTT");
+
+        assert_eq!(track.video_id, 1);
+        assert_eq!(track.language, "en");
+        assert_eq!(track.source, CaptionSource::Transcript.as_str());
+        assert_eq!(
+            caption_file_to_vtt(&track.content, CaptionFormat::Vtt).as_deref(),
+            Some(track.content.as_str())
+        );
+        assert!(track
+            .content
+            .contains("00:00:00.000 --> 00:00:05.000\nMock transcript of video abcdef"));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod public;
+pub mod sql;
+pub mod util;
@@ -0,0 +1,97 @@
+use crate::{
+    auth::util::{get_user_from_cookies, user_has_admin_permission},
+    db::{get_connection, DbPool},
+    error::AppError,
+    models::Video,
+    response::{ApiMessage, ApiMessageResult, ApiResponse, ApiResult},
+    trash::{
+        sql::{get_trashed_video_by_video_id, get_trashed_videos_for_user, restore_video},
+        util::purge_video,
+    },
+};
+use rocket::http::CookieJar;
+use rocket::State;
+
+/// Lists the videos in the user's trash, most recently deleted first
+#[utoipa::path(
+    context_path = "/api/trash",
+    tag = "trash",
+    responses((status = 200, body = VideoListResponse))
+)]
+#[get("/")]
+pub async fn list_trash(cookies: &CookieJar<'_>, pool: &State<DbPool>) -> ApiResult<Vec<Video>> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let videos = get_trashed_videos_for_user(&connection, user.id)?;
+
+    Ok(ApiResponse::ok(videos))
+}
+
+#[utoipa::path(
+    context_path = "/api/trash",
+    tag = "trash",
+    responses((status = 200, body = VideoResponse))
+)]
+#[post("/<id>/restore")]
+pub async fn restore_trashed_video(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<Video> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video = get_trashed_video_by_video_id(&connection, &id)?;
+
+    if video.owner_id != user.id {
+        info!("User {} is not the owner of video {}", user.id, video.id);
+        return Err(AppError::NotFound);
+    }
+
+    let video = restore_video(&connection, video.id)?;
+
+    Ok(ApiResponse::ok(video))
+}
+
+/// Permanently deletes a trashed video without waiting for the retention period
+#[utoipa::path(
+    context_path = "/api/trash",
+    tag = "trash",
+    responses((status = 200, body = ApiMessage))
+)]
+#[delete("/<id>")]
+pub async fn purge_trashed_video(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let mut connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    if !user_has_admin_permission(&user) {
+        info!("User {} can not purge videos", user.id);
+        return Err(AppError::Forbidden);
+    }
+
+    let video = get_trashed_video_by_video_id(&connection, &id)?;
+
+    if !purge_video(&mut connection, &video).await? {
+        // Restored in the meantime
+        return Err(AppError::NotFound);
+    }
+
+    Ok(ApiMessage::ok())
+}
@@ -0,0 +1,74 @@
+extern crate diesel;
+
+use crate::{error::AppResult, models::*};
+use chrono::NaiveDateTime;
+use diesel::{
+    dsl::{now, IntervalDsl},
+    prelude::*,
+};
+
+/// Moves a video to the trash, hiding it everywhere but its owner's trash
+pub fn trash_video(connection: &PgConnection, id: i32) -> AppResult<()> {
+    use crate::schema::videos::dsl;
+
+    diesel::update(crate::schema::videos::table.filter(dsl::id.eq(id)))
+        .set(dsl::deleted_at.eq(now))
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn restore_video(connection: &PgConnection, id: i32) -> AppResult<Video> {
+    use crate::schema::videos::dsl;
+
+    Ok(
+        diesel::update(crate::schema::videos::table.filter(dsl::id.eq(id)))
+            .set(dsl::deleted_at.eq(None::<NaiveDateTime>))
+            .get_result::<Video>(connection)?,
+    )
+}
+
+/// Deletes a video if it is still in the trash, returning the deleted row.
+/// None if it was restored or purged since it was loaded.
+pub fn delete_trashed_video(connection: &PgConnection, id: i32) -> AppResult<Option<Video>> {
+    use crate::schema::videos::dsl;
+
+    Ok(diesel::delete(
+        crate::schema::videos::table
+            .filter(dsl::id.eq(id))
+            .filter(dsl::deleted_at.is_not_null()),
+    )
+    .get_result::<Video>(connection)
+    .optional()?)
+}
+
+pub fn get_trashed_video_by_video_id(connection: &PgConnection, id: &String) -> AppResult<Video> {
+    use crate::schema::videos::dsl;
+
+    Ok(crate::schema::videos::table
+        .filter(dsl::video_id.eq(id.to_owned()))
+        .filter(dsl::deleted_at.is_not_null())
+        .first::<Video>(connection)?)
+}
+
+/// Gets a user's trashed videos, most recently deleted first
+pub fn get_trashed_videos_for_user(
+    connection: &PgConnection,
+    owner_id: i32,
+) -> AppResult<Vec<Video>> {
+    use crate::schema::videos::dsl;
+
+    Ok(crate::schema::videos::table
+        .filter(dsl::owner_id.eq(owner_id))
+        .filter(dsl::deleted_at.is_not_null())
+        .order((dsl::deleted_at.desc(), dsl::id.desc()))
+        .load::<Video>(connection)?)
+}
+
+/// Gets every video that has been in the trash for more than `days`
+pub fn get_videos_trashed_for(connection: &PgConnection, days: i32) -> AppResult<Vec<Video>> {
+    use crate::schema::videos::dsl;
+
+    Ok(crate::schema::videos::table
+        .filter(dsl::deleted_at.lt((now - days.days()).nullable()))
+        .load::<Video>(connection)?)
+}
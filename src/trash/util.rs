@@ -0,0 +1,92 @@
+use crate::{
+    audio::util::remove_cached_audio,
+    db::{env_or, get_connection, DbPool},
+    error::{AppError, AppResult},
+    job::{model::JobKind, util::schedule_job},
+    media::util::release_video_file,
+    models::Video,
+    preview::util::remove_previews,
+    trash::sql::{delete_trashed_video, get_videos_trashed_for},
+};
+use diesel::{Connection, PgConnection};
+use std::{io::ErrorKind, time::Duration};
+
+const DEFAULT_TRASH_RETENTION_DAYS: i32 = 30;
+const DEFAULT_TRASH_PURGE_INTERVAL_SECONDS: u64 = 60 * 60;
+
+/// Permanently deletes a video with its previews and cached audio, and its file
+/// unless another video has the same content.
+///
+/// The row is only gone once the file is, so a failed unlink can be retried
+/// instead of leaving a file no video points to. Returns false, purging nothing,
+/// if the video was restored since it was loaded.
+pub async fn purge_video(connection: &mut PgConnection, video: &Video) -> AppResult<bool> {
+    let purged = connection.transaction::<_, AppError, _>(|| {
+        // The deleted row, since the video's file may have changed since it was loaded
+        let deleted = match delete_trashed_video(connection, video.id)? {
+            Some(deleted) => deleted,
+            None => return Ok(false),
+        };
+        // Other videos with the same content keep the file
+        let path = match release_video_file(connection, &deleted)? {
+            Some(path) => path,
+            None => return Ok(true),
+        };
+        match std::fs::remove_file(&path) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                warn!("File {} of video {} was already gone", path, video.video_id);
+                Ok(true)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to delete file {} of video {} with error: {}",
+                    path, video.video_id, e
+                );
+                Err(AppError::Io(e))
+            }
+        }
+    })?;
+    if purged {
+        remove_previews(video).await;
+        remove_cached_audio(video).await;
+    }
+    Ok(purged)
+}
+
+/// Purges every video that has been in the trash for `retention_days`
+pub async fn purge_expired_videos(pool: &DbPool, retention_days: i32) -> AppResult<()> {
+    let mut connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    let expired = get_videos_trashed_for(&connection, retention_days)?;
+    let mut failed = 0;
+    for video in expired {
+        match purge_video(&mut connection, &video).await {
+            Ok(true) => info!("Purged video {} from the trash", video.video_id),
+            Ok(false) => info!("Video {} was restored, not purging it", video.video_id),
+            Err(e) => {
+                warn!("Failed to purge video {} ({})", video.video_id, e);
+                failed += 1;
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(AppError::Internal(format!(
+            "failed to purge {} videos from the trash",
+            failed
+        ))),
+    }
+}
+
+/// Queues a purge of videos that have been in the trash for `TRASH_RETENTION_DAYS`
+/// every `TRASH_PURGE_INTERVAL_SECONDS`
+pub async fn schedule_purge_job(pool: DbPool) {
+    let retention_days = env_or("TRASH_RETENTION_DAYS", DEFAULT_TRASH_RETENTION_DAYS);
+    let period = Duration::from_secs(env_or(
+        "TRASH_PURGE_INTERVAL_SECONDS",
+        DEFAULT_TRASH_PURGE_INTERVAL_SECONDS,
+    ));
+    schedule_job(pool, JobKind::PurgeTrash { retention_days }, period).await
+}
@@ -1,20 +1,3 @@
-#[macro_export]
-macro_rules! make_json_response {
-    ($status:expr, $message:expr, $data:expr) => {
-        RawJson(json!({
-            "status": $status as i32,
-            "message": $message,
-            "data": $data,
-        }).to_string())
-    };
-    ($status:expr, $message:expr) => {
-        RawJson(json!({
-            "status": $status as i32,
-            "message": $message,
-        }).to_string())
-    }
-}
-
 #[macro_export]
 macro_rules! unwrap_or_return_result {
     ($r:expr, $s:expr) => {
@@ -44,6 +27,7 @@ macro_rules! unwrap_or_return_option {
 use rand::Rng;
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::http::Header;
+use rocket::tokio::fs;
 use rocket::{Request, Response};
 
 pub struct CORS;
@@ -78,3 +62,12 @@ pub fn make_random_string(length: usize) -> String {
         })
         .collect()
 }
+
+/// Removes a file, logging instead of failing when it can not be removed
+pub async fn remove_file_if_exists(path: &str) {
+    if std::path::Path::new(path).exists() {
+        if let Err(e) = fs::remove_file(path).await {
+            warn!("Failed to remove file {} with error: {}", path, e);
+        }
+    }
+}
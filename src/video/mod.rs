@@ -1,4 +1,5 @@
 pub mod model;
+pub mod password;
 pub mod public;
 pub mod sql;
 pub mod util;
@@ -1,8 +1,271 @@
+use crate::{
+    caption::model::CaptionTrackInfo,
+    models::{Chapter, Video},
+    tag::util::normalize_tag,
+};
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct VideoInfo {
     pub name: Option<String>,
     pub description: Option<String>,
     pub share: Option<Vec<String>>,
+    pub visibility: Option<Visibility>,
+    /// Sets the password viewers need to watch the video. An empty string removes it.
+    pub password: Option<String>,
+    /// Moves the video into the folder with this `folder_id`. An empty string moves it to the top level.
+    pub folder: Option<String>,
+    /// Replaces the video's tags
+    pub tags: Option<Vec<String>>,
+    /// Whether viewers can download the original file. The owner always can.
+    pub downloads_enabled: Option<bool>,
+}
+
+/// A video along with everything shown next to it by the player
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VideoDetails {
+    #[serde(flatten)]
+    pub video: Video,
+    pub tags: Vec<String>,
+    pub captions: Vec<CaptionTrackInfo>,
+    pub chapters: Vec<Chapter>,
+}
+
+/// A newly uploaded video
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VideoUpload {
+    #[serde(flatten)]
+    pub video: Video,
+    /// Ids of the uploader's other videos with the same content, warning that
+    /// the upload is a duplicate
+    pub duplicate_of: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VideoUnlock {
+    pub password: String,
+}
+
+/// Who is able to see a video, stored as text in `videos.visibility`.
+///
+/// The owner, admins and users the video is shared with can always see it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Only the owner and users the video is shared with
+    #[default]
+    Private,
+    /// Anyone with the link, but never listed
+    Unlisted,
+    /// Any logged in user
+    Internal,
+    /// Anyone, including anonymous users
+    Public,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Private => "private",
+            Visibility::Unlisted => "unlisted",
+            Visibility::Internal => "internal",
+            Visibility::Public => "public",
+        }
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "private" => Ok(Visibility::Private),
+            "unlisted" => Ok(Visibility::Unlisted),
+            "internal" => Ok(Visibility::Internal),
+            "public" => Ok(Visibility::Public),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Processing state of an uploaded video, stored as text in `videos.status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoStatus {
+    Processing,
+    Ready,
+    Failed,
+}
+
+impl VideoStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VideoStatus::Processing => "processing",
+            VideoStatus::Ready => "ready",
+            VideoStatus::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for VideoStatus {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "processing" => Ok(VideoStatus::Processing),
+            "ready" => Ok(VideoStatus::Ready),
+            "failed" => Ok(VideoStatus::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Query parameters accepted when listing videos
+#[derive(Debug, FromForm, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VideoListQuery {
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// One of `date`, `name`, `duration` or `size`
+    pub sort: Option<String>,
+    /// Either `asc` or `desc`
+    pub order: Option<String>,
+    /// Only include videos whose name contains this (case insensitive)
+    pub name: Option<String>,
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    pub status: Option<String>,
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoSort {
+    Date,
+    Name,
+    Duration,
+    Size,
+}
+
+impl FromStr for VideoSort {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "date" => Ok(VideoSort::Date),
+            "name" => Ok(VideoSort::Name),
+            "duration" => Ok(VideoSort::Duration),
+            "size" => Ok(VideoSort::Size),
+            _ => Err(()),
+        }
+    }
+}
+
+impl VideoSort {
+    /// Makes the cursor pointing after `video` when sorting this way
+    pub fn cursor_for(&self, video: &Video) -> String {
+        match self {
+            VideoSort::Date => format!(
+                "{}:{}",
+                video.created_at.format(CURSOR_DATE_FORMAT),
+                video.id
+            ),
+            VideoSort::Name => format!("{}:{}", video.video_name, video.id),
+            VideoSort::Duration => format!("{}:{}", video.video_length, video.id),
+            VideoSort::Size => format!("{}:{}", video.file_size, video.id),
+        }
+    }
+}
+
+const CURSOR_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// The sort value and id of the last video on the previous page
+#[derive(Debug, Clone, PartialEq)]
+pub enum VideoCursor {
+    Date(NaiveDateTime, i32),
+    Name(String, i32),
+    Duration(f64, i32),
+    Size(i64, i32),
+}
+
+impl VideoCursor {
+    fn parse(sort: VideoSort, cursor: &str) -> Option<Self> {
+        let (value, id) = cursor.rsplit_once(':')?;
+        let id = id.parse::<i32>().ok()?;
+        match sort {
+            VideoSort::Date => NaiveDateTime::parse_from_str(value, CURSOR_DATE_FORMAT)
+                .ok()
+                .map(|v| VideoCursor::Date(v, id)),
+            VideoSort::Name => Some(VideoCursor::Name(value.to_string(), id)),
+            VideoSort::Duration => value.parse().ok().map(|v| VideoCursor::Duration(v, id)),
+            VideoSort::Size => value.parse().ok().map(|v| VideoCursor::Size(v, id)),
+        }
+    }
+}
+
+/// A validated `VideoListQuery`
+#[derive(Debug)]
+pub struct VideoListFilter {
+    pub sort: VideoSort,
+    pub descending: bool,
+    pub cursor: Option<VideoCursor>,
+    pub limit: i64,
+    pub name: Option<String>,
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    pub status: Option<VideoStatus>,
+    pub tag: Option<String>,
+}
+
+const DEFAULT_PAGE_SIZE: i64 = 25;
+const MAX_PAGE_SIZE: i64 = 100;
+
+impl TryFrom<VideoListQuery> for VideoListFilter {
+    type Error = &'static str;
+    fn try_from(query: VideoListQuery) -> Result<Self, &'static str> {
+        let sort = match &query.sort {
+            Some(sort) => sort.parse::<VideoSort>().map_err(|_| "Invalid sort")?,
+            None => VideoSort::Date,
+        };
+        let descending = match query.order.as_deref() {
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => return Err("Invalid order"),
+            // Newest first, everything else in ascending order
+            None => sort == VideoSort::Date,
+        };
+        let cursor = match &query.cursor {
+            Some(cursor) => Some(VideoCursor::parse(sort, cursor).ok_or("Invalid cursor")?),
+            None => None,
+        };
+        let status = match &query.status {
+            Some(status) => Some(
+                status
+                    .parse::<VideoStatus>()
+                    .map_err(|_| "Invalid status")?,
+            ),
+            None => None,
+        };
+
+        Ok(VideoListFilter {
+            sort,
+            descending,
+            cursor,
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+            name: query.name.filter(|name| !name.is_empty()),
+            min_duration: query.min_duration,
+            max_duration: query.max_duration,
+            status,
+            tag: query.tag.as_deref().and_then(normalize_tag),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VideoPage {
+    pub videos: Vec<Video>,
+    pub next_cursor: Option<String>,
 }
@@ -0,0 +1,148 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use rocket::http::{Cookie, CookieJar, SameSite};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::unwrap_or_return_result;
+
+/// How long a grant cookie lets a viewer watch a password protected video
+const GRANT_LIFETIME_SECONDS: i64 = 30 * 60;
+/// Number of unlock attempts allowed per client and video within `ATTEMPT_WINDOW`
+const MAX_UNLOCK_ATTEMPTS: usize = 5;
+const ATTEMPT_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+pub fn hash_video_password(password: &str) -> Option<String> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = unwrap_or_return_result!(
+        Argon2::default().hash_password(password.as_bytes(), &salt),
+        "Failed to hash video password"
+    );
+    Some(hash.to_string())
+}
+
+pub fn verify_video_password(password: &str, password_hash: &str) -> bool {
+    let parsed_hash = match PasswordHash::new(password_hash) {
+        Ok(hash) => hash,
+        Err(e) => {
+            warn!("Stored video password hash is invalid (error {})", e);
+            return false;
+        }
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok()
+}
+
+fn grant_cookie_name(video_id: &str) -> String {
+    format!("video_grant_{}", video_id)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Adds a private (encrypted and signed) cookie granting access to `video_id`
+/// for `GRANT_LIFETIME_SECONDS`. The cookie is sent to the whole API, since the video
+/// is also served under `/api/videos` and inside playlists.
+pub fn add_video_grant(cookies: &CookieJar<'_>, video_id: &str) {
+    let expires_at = unix_now() + GRANT_LIFETIME_SECONDS;
+    cookies.add_private(
+        Cookie::build(
+            grant_cookie_name(video_id),
+            format!("{}:{}", video_id, expires_at),
+        )
+        .path("/api")
+        .max_age(rocket::time::Duration::seconds(GRANT_LIFETIME_SECONDS))
+        .same_site(SameSite::Lax)
+        .finish(),
+    );
+}
+
+pub fn has_valid_video_grant(cookies: &CookieJar<'_>, video_id: &str) -> bool {
+    let cookie = match cookies.get_private(&grant_cookie_name(video_id)) {
+        Some(cookie) => cookie,
+        None => return false,
+    };
+    grant_allows(cookie.value(), video_id, unix_now())
+}
+
+/// Checks a grant cookie value (`<video_id>:<expires_at>`) against the video and the time
+fn grant_allows(value: &str, video_id: &str, now: i64) -> bool {
+    match value.rsplit_once(':') {
+        Some((grant_video_id, expires_at)) => {
+            grant_video_id == video_id
+                && expires_at
+                    .parse::<i64>()
+                    .map(|expires_at| expires_at > now)
+                    .unwrap_or(false)
+        }
+        None => false,
+    }
+}
+
+/// Keeps track of recent unlock attempts per client and video, managed as Rocket state.
+#[derive(Default)]
+pub struct UnlockRateLimiter {
+    attempts: Mutex<HashMap<(IpAddr, String), Vec<Instant>>>,
+}
+
+impl UnlockRateLimiter {
+    /// Records an attempt, returning false if the client has used up its attempts.
+    pub fn try_attempt(&self, ip: IpAddr, video_id: &str) -> bool {
+        let mut attempts = match self.attempts.lock() {
+            Ok(attempts) => attempts,
+            Err(e) => {
+                warn!("Unlock rate limiter lock was poisoned (error {})", e);
+                return false;
+            }
+        };
+        let now = Instant::now();
+        attempts.retain(|_, times| {
+            times.retain(|time| now.duration_since(*time) < ATTEMPT_WINDOW);
+            !times.is_empty()
+        });
+
+        let times = attempts.entry((ip, video_id.to_string())).or_default();
+        if times.len() >= MAX_UNLOCK_ATTEMPTS {
+            return false;
+        }
+        times.push(now);
+        true
+    }
+
+    pub fn reset(&self, ip: IpAddr, video_id: &str) {
+        if let Ok(mut attempts) = self.attempts.lock() {
+            attempts.remove(&(ip, video_id.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::grant_allows;
+
+    #[test]
+    fn grant_allows_its_video_until_it_expires() {
+        assert!(grant_allows("abc:1000", "abc", 999));
+        assert!(!grant_allows("abc:1000", "abc", 1000));
+        assert!(!grant_allows("abc:1000", "abd", 999));
+    }
+
+    #[test]
+    fn grant_rejects_malformed_values() {
+        assert!(!grant_allows("abc", "abc", 0));
+        assert!(!grant_allows("abc:", "abc", 0));
+        assert!(!grant_allows("abc:soon", "abc", 0));
+        assert!(!grant_allows(":1000", "abc", 0));
+    }
+}
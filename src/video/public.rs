@@ -1,243 +1,366 @@
 use crate::{
-    auth::{sql::get_user_by_user_id, util::oauth_token_is_valid},
-    make_json_response,
-    models::{Video, VideoNoId},
-    unwrap_or_return_option,
-    video::sql::{generate_new_video_id, get_video_by_video_id, insert_new_video},
+    auth::{
+        sql::get_user_by_user_id,
+        util::{get_user_from_cookies, oauth_token_is_valid},
+    },
+    caption::{model::CaptionTrackInfo, sql::get_caption_tracks_for_video},
+    chapter::sql::get_chapters_for_video,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    event::{model::VideoEventKind, util::VideoEvents},
+    folder::sql::get_folder_by_folder_id,
+    media::{
+        sql::get_duplicate_video_ids,
+        util::{release_video_file, HashingWriter, MEDIA_BLOB_FOLDER},
+    },
+    models::{Video, VideoEdit, VideoNoId},
+    response::{ApiMessage, ApiMessageResult, ApiResponse, ApiResult},
+    tag::{
+        sql::{get_tags_for_video, set_video_tags},
+        util::normalize_tags,
+    },
+    trash::sql::trash_video,
+    util::remove_file_if_exists,
+    video::{
+        model::{
+            VideoDetails, VideoListFilter, VideoListQuery, VideoPage, VideoStatus, VideoUnlock,
+            VideoUpload, Visibility,
+        },
+        password::{
+            add_video_grant, hash_video_password, verify_video_password, UnlockRateLimiter,
+        },
+        sql::{
+            delete_video_with_id, generate_new_video_id, get_video_by_video_id,
+            get_videos_for_user, insert_video_share, update_video,
+        },
+    },
 };
-use rocket::response::content::RawJson;
+use diesel::Connection;
 use rocket::{
     data::{Data, ToByteUnit},
     http::CookieJar,
+    State,
 };
 use rocket_seek_stream::SeekStream;
 use sanitize_html::rules::predefined::DEFAULT;
 use sanitize_html::sanitize_str;
-use serde_json::json;
-use std::path::PathBuf;
+use std::net::IpAddr;
 
-use super::util::{get_filename_ending, valid_video_filename_ending};
+use super::util::{
+    clean_original_filename, get_filename_ending, get_video_access, insert_upload,
+    upload_temp_path, user_can_view_video, valid_video_filename_ending, VideoAccess,
+};
 
-#[get("/<id>?<one_time>")]
-#[allow(unused_variables)]
-pub async fn get_video_info(
-    id: String,
-    one_time: Option<String>,
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    params(VideoListQuery),
+    responses((status = 200, body = VideoPageResponse))
+)]
+#[get("/?<query..>")]
+pub async fn list_videos(
+    query: VideoListQuery,
     cookies: &CookieJar<'_>,
-) -> RawJson<String> {
-    // Implement one time code
-    let user_id = match cookies.get("user_id") {
-        Some(cookie) => cookie.value().to_string(),
-        None => {
-            info!("No user_id cookie found");
-            return make_json_response!(401, "Unauthorized");
-        }
-    };
-    let oauth_type = match cookies.get("oauth") {
-        Some(cookie) => cookie.value().to_string(),
-        None => {
-            info!("No oauth type cookie found");
-            return make_json_response!(401, "Unauthorized");
-        }
-    };
-
-    let token = match cookies.get_private("token") {
-        Some(cookie) => cookie.value().to_string(),
-        None => {
-            info!("No token cookie found");
-            return make_json_response!(401, "Unauthorized");
+    pool: &State<DbPool>,
+) -> ApiResult<VideoPage> {
+    let user = get_user_from_cookies(pool, cookies)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let filter = match VideoListFilter::try_from(query) {
+        Ok(filter) => filter,
+        Err(e) => {
+            info!("Invalid video list query ({})", e);
+            return Err(AppError::BadRequest);
         }
     };
 
-    if !oauth_token_is_valid(oauth_type.clone(), token.clone(), user_id.clone()).await {
-        info!("User {} had an invalid token", user_id);
-        return make_json_response!(401, "Unauthorized");
-    }
+    let mut videos = get_videos_for_user(&connection, user.id, &filter)?;
 
-    let user = match get_user_by_user_id(&user_id) {
-        Some(user) => user,
-        None => {
-            info!("No user found with user_id {}", user_id);
-            return make_json_response!(401, "Unauthorized");
-        }
+    let next_cursor = if videos.len() as i64 > filter.limit {
+        videos.truncate(filter.limit as usize);
+        videos.last().map(|video| filter.sort.cursor_for(video))
+    } else {
+        None
     };
 
-    let video: Video = match get_video_by_video_id(&id) {
-        Some(video) => video,
-        None => {
-            info!("No video found with video_id {}", id);
-            return make_json_response!(404, "Not found");
-        }
-    };
+    Ok(ApiResponse::ok(VideoPage {
+        videos,
+        next_cursor,
+    }))
+}
 
-    if video.owner_id != user.id && !crate::auth::util::user_is_admin(user) {
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    responses((status = 200, body = VideoDetailsResponse))
+)]
+#[get("/<id>?<one_time>")]
+#[allow(unused_variables)]
+pub async fn get_video_info(
+    id: String,
+    one_time: Option<String>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiResult<VideoDetails> {
+    // Implement one time code
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video: Video = get_video_by_video_id(&connection, &id)?;
+
+    match get_video_access(&connection, &video, user.as_ref(), cookies)? {
+        VideoAccess::Allowed => {
+            let tags = get_tags_for_video(&connection, video.id)?;
+            let captions = get_caption_tracks_for_video(&connection, video.id)?
+                .into_iter()
+                .map(|(language, label, source)| {
+                    CaptionTrackInfo::new(&video.video_id, language, label, source)
+                })
+                .collect();
+            let chapters = get_chapters_for_video(&connection, video.id)?;
+            Ok(ApiResponse::ok(VideoDetails {
+                video,
+                tags,
+                captions,
+                chapters,
+            }))
+        }
+        VideoAccess::PasswordRequired => Err(AppError::PasswordRequired),
         // TODO : One time password
-        return make_json_response!(401, "Unauthorized");
+        VideoAccess::Denied => match user {
+            Some(_) => Err(AppError::Forbidden),
+            None => Err(AppError::Unauthorized),
+        },
     }
-
-    make_json_response!(200, "Ok", video)
 }
 
-#[get("/<id>/<filename>?<one_time>")]
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    responses((status = 200, description = "The video file, supports range requests"))
+)]
+// Ranked after the other `/<id>/...` routes so it does not shadow them
+#[get("/<id>/<filename>?<one_time>", rank = 2)]
 #[allow(unused_variables)]
 pub async fn get_video<'a>(
     id: String,
     filename: String,
     one_time: Option<String>,
     cookies: &CookieJar<'_>,
-) -> Option<SeekStream<'a>> {
+    pool: &State<DbPool>,
+) -> AppResult<SeekStream<'a>> {
     // TODO : Implement one time code
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
 
-    let user_id = match cookies.get("user_id") {
-        Some(cookie) => cookie.value().to_string(),
-        None => {
-            info!("No user_id cookie found");
-            return None;
-        }
-    };
-    let oauth_type = match cookies.get("oauth") {
-        Some(cookie) => cookie.value().to_string(),
-        None => {
-            info!("No oauth type cookie found");
-            return None;
-        }
-    };
+    let video: Video = get_video_by_video_id(&connection, &id)?;
 
-    let token = match cookies.get_private("token") {
-        Some(cookie) => cookie.value().to_string(),
-        None => {
-            info!("No token cookie found");
-            return None;
-        }
-    };
+    if get_video_access(&connection, &video, user.as_ref(), cookies)? != VideoAccess::Allowed {
+        // TODO : One time password
+        return Err(AppError::NotFound);
+    }
+
+    SeekStream::from_path(video.video_path).map_err(AppError::from_file_error)
+}
 
-    if !oauth_token_is_valid(oauth_type.clone(), token.clone(), user_id.clone()).await {
-        info!("User {} had an invalid token", user_id);
-        return None;
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    request_body = VideoUnlock,
+    responses((status = 200, body = ApiMessage))
+)]
+#[post("/<id>/unlock", data = "<unlock>", format = "json")]
+pub async fn unlock_video(
+    id: String,
+    unlock: rocket::serde::json::Json<VideoUnlock>,
+    client_ip: IpAddr,
+    rate_limiter: &State<UnlockRateLimiter>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
+    let user = get_user_from_cookies(pool, cookies).await?;
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let video: Video = get_video_by_video_id(&connection, &id)?;
+
+    if !user_can_view_video(&connection, &video, user.as_ref())? {
+        return match user {
+            Some(_) => Err(AppError::Forbidden),
+            None => Err(AppError::Unauthorized),
+        };
     }
 
-    let user = match get_user_by_user_id(&user_id) {
-        Some(user) => user,
+    let password_hash = match &video.password_hash {
+        Some(password_hash) => password_hash,
         None => {
-            info!("No user found with user_id {}", user_id);
-            return None;
+            info!("Video {} is not password protected", video.video_id);
+            return Err(AppError::BadRequest);
         }
     };
 
-    let video: Video = unwrap_or_return_option!(get_video_by_video_id(&id), "Video not found");
+    if !rate_limiter.try_attempt(client_ip, &video.video_id) {
+        info!(
+            "Too many unlock attempts for video {} from {}",
+            video.video_id, client_ip
+        );
+        return Err(AppError::TooManyRequests);
+    }
 
-    if video.owner_id != user.id && !crate::auth::util::user_is_admin(user) {
-        // TODO : One time password
-        return None;
+    if !verify_video_password(&unlock.password, password_hash) {
+        info!("Incorrect password for video {}", video.video_id);
+        return Err(AppError::Unauthorized);
     }
 
-    SeekStream::from_path(video.video_path).ok()
+    rate_limiter.reset(client_ip, &video.video_id);
+    add_video_grant(cookies, &video.video_id);
+
+    Ok(ApiMessage::ok())
 }
 
+/// Moves a video to its owner's trash, see `/api/trash`
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    responses((status = 200, body = ApiMessage))
+)]
 #[delete("/<id>")]
-pub async fn delete_video(id: String, cookies: &CookieJar<'_>) -> RawJson<String> {
+pub async fn delete_video(
+    id: String,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+) -> ApiMessageResult {
     let user_id = match cookies.get("user_id") {
         Some(cookie) => cookie.value().to_string(),
         None => {
             info!("No user_id cookie found");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
     let oauth_type = match cookies.get("oauth") {
         Some(cookie) => cookie.value().to_string(),
-        None => return make_json_response!(401, "Unauthorized"),
+        None => return Err(AppError::Unauthorized),
     };
 
     let token = match cookies.get_private("token") {
         Some(cookie) => cookie.value().to_string(),
-        None => return make_json_response!(401, "Unauthorized"),
+        None => return Err(AppError::Unauthorized),
     };
 
-    if !oauth_token_is_valid(oauth_type.clone(), token.clone(), user_id.clone()).await {
-        return make_json_response!(401, "Unauthorized");
+    if !oauth_token_is_valid(pool, oauth_type.clone(), token.clone(), user_id.clone()).await? {
+        return Err(AppError::Unauthorized);
     }
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
 
-    let user = match crate::auth::sql::get_user_by_user_id(&user_id) {
-        Some(u) => u,
-        None => {
+    let user = match get_user_by_user_id(&connection, &user_id) {
+        Ok(u) => u,
+        Err(AppError::NotFound) => {
             info!("Failed to get user with id {}", user_id);
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
+        Err(e) => return Err(e),
     };
 
-    let video = match get_video_by_video_id(&id) {
-        Some(video) => video,
-        None => {
-            info!("Could not find video with id {}", id);
-            return make_json_response!(400, "Bad Request");
-        }
-    };
+    let video = get_video_by_video_id(&connection, &id)?;
 
-    if video.owner_id != user.id && !crate::auth::util::user_is_admin(&user.user_id) {
+    if video.owner_id != user.id && !crate::auth::util::user_is_admin(&connection, &user.user_id)? {
         info!("User did not own video that was attempted to be deleted.");
-        return make_json_response!(401, "Unauthorized");
-    }
-
-    if !crate::video::sql::delete_video_with_id(video.id) {
-        info!("Failed to remove video from database.");
-        return make_json_response!(500, "Internal Server Error");
+        return Err(AppError::Forbidden);
     }
 
-    match rocket::tokio::fs::remove_file(video.video_path.clone()).await {
-        Ok(_) => (),
-        Err(e) => {
-            warn!("Failed to delete video after removing video from database! (error {}) Please find it here: {}", e, video.video_path);
-            return make_json_response!(500, "Internal Server Error");
-        }
-    }
+    trash_video(&connection, video.id)?;
 
-    make_json_response!(200, "Ok")
+    Ok(ApiMessage::ok())
 }
 
-#[post("/add?<name>", data = "<video>")]
-pub async fn add_video(name: String, video: Data<'_>, cookies: &CookieJar<'_>) -> RawJson<String> {
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    request_body(content = String, description = "The video file", content_type = "application/octet-stream"),
+    responses((status = 200, body = VideoUploadResponse))
+)]
+#[post("/add?<name>&<visibility>&<tags>", data = "<video>")]
+pub async fn add_video(
+    name: String,
+    visibility: Option<String>,
+    tags: Option<String>,
+    video: Data<'_>,
+    cookies: &CookieJar<'_>,
+    pool: &State<DbPool>,
+    events: &State<VideoEvents>,
+) -> ApiResult<VideoUpload> {
     let user_id = match cookies.get("user_id") {
         Some(cookie) => cookie.value().to_string(),
         None => {
             info!("No user_id cookie found");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
     let oauth_type = match cookies.get("oauth") {
         Some(cookie) => cookie.value().to_string(),
-        None => return make_json_response!(401, "Unauthorized"),
+        None => return Err(AppError::Unauthorized),
     };
 
     let token = match cookies.get_private("token") {
         Some(cookie) => cookie.value().to_string(),
-        None => return make_json_response!(401, "Unauthorized"),
+        None => return Err(AppError::Unauthorized),
     };
 
-    if !oauth_token_is_valid(oauth_type.clone(), token.clone(), user_id.clone()).await {
-        return make_json_response!(401, "Unauthorized");
+    if !oauth_token_is_valid(pool, oauth_type.clone(), token.clone(), user_id.clone()).await? {
+        return Err(AppError::Unauthorized);
     }
-
-    let user = match get_user_by_user_id(&user_id) {
-        Some(user) => user,
-        None => {
+    // Not a request guard, the connection is given back while the body is streamed
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+
+    let user = match get_user_by_user_id(&connection, &user_id) {
+        Ok(user) => user,
+        Err(AppError::NotFound) => {
             info!("No user found with user_id {}", user_id);
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
+        Err(e) => return Err(e),
+    };
+
+    let visibility = match visibility {
+        Some(visibility) => match visibility.parse::<Visibility>() {
+            Ok(visibility) => visibility,
+            Err(_) => {
+                info!("Invalid visibility {}", visibility);
+                return Err(AppError::BadRequest);
+            }
+        },
+        None => Visibility::default(),
     };
 
+    let original_filename = clean_original_filename(&name);
     let name = name.replace("..", "").replace("/", "");
 
     let mut name_sanitized = match sanitize_str(&DEFAULT, &name) {
         Ok(name_sanitized) => name_sanitized.replace("..", "").replace("/", ""),
         Err(e) => {
-            warn!("Failed to sanitize name {} with error: {}", name, e);
-            return make_json_response!(500, "Internal Server Error");
+            return Err(AppError::Internal(format!(
+                "failed to sanitize name {} ({})",
+                name, e
+            )));
         }
     };
 
     if !valid_video_filename_ending(&name_sanitized) {
         info!("Invalid video filename {}", name_sanitized);
-        return make_json_response!(400, "Bad Request");
+        return Err(AppError::BadRequest);
     }
 
     // Safe unwrap, would have returned bad request if None
@@ -256,134 +379,171 @@ pub async fn add_video(name: String, video: Data<'_>, cookies: &CookieJar<'_>) -
             Ok(_) => (),
             Err(e) => {
                 warn!("Failed to create folder {} with error: {}", folder, e);
-                return make_json_response!(500, "Internal Server Error");
+                return Err(AppError::Io(e));
             }
         }
     }
-    let video_id = generate_new_video_id();
-    let file_name = format!("{}.{}", video_id, ending);
-    let file_path = format!("{}/{}", folder, file_name);
-    let file_path_buf = PathBuf::from(file_path.clone());
-    let file_out = match rocket::tokio::fs::File::create(file_path_buf.clone()).await {
-        Ok(file_out) => file_out,
-        Err(e) => {
+    if !std::path::Path::new(MEDIA_BLOB_FOLDER).exists() {
+        if let Err(e) = rocket::tokio::fs::create_dir_all(MEDIA_BLOB_FOLDER).await {
             warn!(
-                "Failed to create file {} with error: {}",
-                file_path_buf.to_str().unwrap_or("\"Failed on unwrap\""),
-                e
+                "Failed to create folder {} with error: {}",
+                MEDIA_BLOB_FOLDER, e
             );
-            return make_json_response!(500, "Internal Server Error");
+            return Err(AppError::Io(e));
+        }
+    }
+    let video_id = generate_new_video_id(&connection)?;
+    // Uploads can take long enough to use up the pool otherwise
+    drop(connection);
+    // The upload only gets its real path once its video is committed, so a failed
+    // upload or insert never leaves a file without a video behind
+    let temp_path = upload_temp_path(&folder, &video_id, &ending);
+    let file_out = match rocket::tokio::fs::File::create(&temp_path).await {
+        Ok(file_out) => file_out,
+        Err(e) => {
+            warn!("Failed to create file {} with error: {}", temp_path, e);
+            return Err(AppError::Io(e));
         }
     };
-    match video_file_stream.stream_to(file_out).await {
-        Ok(_) => {
-            let video = VideoNoId {
-                owner_id: user.id,
-                video_id: video_id.clone(),
-                video_url: format!("/api/video/{}/{}", video_id, name_sanitized),
-                video_path: file_path.clone(),
-                video_name: name_sanitized,
-                // TODO : Optimize
-                video_length: match ffprobe::ffprobe(&file_path) {
-                    Ok(probe) => {
-                        let duration = match &probe.streams[0].duration {
-                            Some(duration) => duration.to_owned(),
-                            None => {
-                                warn!("Failed to get duration from video");
-                                String::from("-1.0")
-                            }
-                        };
-                        match duration.parse::<f64>() {
-                            Ok(duration) => duration,
-                            Err(e) => {
-                                warn!(
-                                    "Failed to parse duration {} from video with error: {}",
-                                    duration, e
-                                );
-                                -1.0
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        warn!("Failed to probe for video length");
-                        -1.0
-                    }
-                },
-                video_desc: String::default(),
-                thumbnail_path: None,
-            };
-            match insert_new_video(&video) {
-                Some(_) => make_json_response!(200, "Ok", video),
-                None => make_json_response!(500, "Internal Server Error"),
-            }
+    let mut file_out = HashingWriter::new(file_out);
+    let written = match video_file_stream.stream_to(&mut file_out).await {
+        Ok(written) => written,
+        Err(e) => {
+            warn!("Failed to add video {} with error : {}", temp_path, e);
+            remove_file_if_exists(&temp_path).await;
+            return Err(AppError::Io(e));
+        }
+    };
+    let sha256 = file_out.finish();
+    let connection = match get_connection(pool).await {
+        Some(connection) => connection,
+        None => {
+            remove_file_if_exists(&temp_path).await;
+            return Err(AppError::ServiceUnavailable);
         }
+    };
+
+    let mut video = VideoNoId {
+        owner_id: user.id,
+        video_id: video_id.clone(),
+        video_url: format!("/api/video/{}/{}", video_id, name_sanitized),
+        video_path: String::new(),
+        video_name: name_sanitized,
+        // Unknown until the video is probed
+        video_length: -1.0,
+        video_desc: String::default(),
+        thumbnail_path: None,
+        visibility: visibility.as_str().to_string(),
+        password_hash: None,
+        file_size: written.written as i64,
+        // Processing until the file is at `video_path` and has been probed
+        status: VideoStatus::Processing.as_str().to_string(),
+        folder_id: None,
+        source_video_id: None,
+        original_filename,
+        downloads_enabled: true,
+        sha256: Some(sha256.clone()),
+    };
+    let tags = tags.map(|tags| normalize_tags(&tags.split(',').collect::<Vec<&str>>()));
+    let inserted = insert_upload(&connection, &mut video, &ending, tags.as_deref());
+    let inserted = match inserted {
+        Ok(inserted) => inserted,
         Err(e) => {
-            warn!(
-                "Failed to add video {} with error : {}",
-                file_path_buf.to_str().unwrap_or("\"Failed on unwrap\""),
-                e
-            );
-            match rocket::tokio::fs::remove_file(file_path_buf.clone()).await {
-                Ok(_) => (),
-                Err(e) => warn!(
-                    "Failed to remove file {} with error : {}",
-                    file_path_buf.to_str().unwrap_or("\"Failed on unwrap\""),
-                    e
-                ),
-            }
-            make_json_response!(500, "Internal Server Error")
+            remove_file_if_exists(&temp_path).await;
+            return Err(e);
+        }
+    };
+
+    // The content is only stored once, so the upload is dropped if another video
+    // already stored it. Otherwise (or if that upload is still being moved into
+    // place) this upload becomes the stored file.
+    if std::path::Path::new(&inserted.video_path).exists() {
+        remove_file_if_exists(&temp_path).await;
+    } else if let Err(e) = rocket::tokio::fs::rename(&temp_path, &inserted.video_path).await {
+        warn!(
+            "Failed to move upload {} to {} with error: {}",
+            temp_path, inserted.video_path, e
+        );
+        remove_file_if_exists(&temp_path).await;
+        let unused_path = connection.transaction::<_, AppError, _>(|| {
+            delete_video_with_id(&connection, inserted.id)?;
+            release_video_file(&connection, &inserted)
+        })?;
+        if let Some(path) = unused_path {
+            remove_file_if_exists(&path).await;
         }
+        return Err(AppError::Io(e));
+    }
+    events.publish(&inserted, VideoEventKind::UploadReceived);
+
+    let duplicate_of = get_duplicate_video_ids(&connection, user.id, &sha256, inserted.id)?;
+    if !duplicate_of.is_empty() {
+        info!(
+            "Video {} is a duplicate of {}",
+            inserted.video_id,
+            duplicate_of.join(", ")
+        );
     }
+
+    Ok(ApiResponse::ok(VideoUpload {
+        video: inserted,
+        duplicate_of,
+    }))
 }
 
+#[utoipa::path(
+    context_path = "/api/video",
+    tag = "video",
+    request_body = VideoInfo,
+    responses((status = 200, body = VideoResponse))
+)]
 #[post("/edit?<id>", data = "<info>", format = "json")]
 pub async fn edit_video(
     id: String,
     mut info: rocket::serde::json::Json<crate::video::model::VideoInfo>,
     cookies: &CookieJar<'_>,
-) -> RawJson<String> {
+    pool: &State<DbPool>,
+) -> ApiResult<Video> {
     let user_id = match cookies.get("user_id") {
         Some(cookie) => cookie.value().to_string(),
         None => {
             info!("No user_id cookie found");
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
     };
     let oauth_type = match cookies.get("oauth") {
         Some(cookie) => cookie.value().to_string(),
-        None => return make_json_response!(401, "Unauthorized"),
+        None => return Err(AppError::Unauthorized),
     };
 
     let token = match cookies.get_private("token") {
         Some(cookie) => cookie.value().to_string(),
-        None => return make_json_response!(401, "Unauthorized"),
+        None => return Err(AppError::Unauthorized),
     };
 
-    if !oauth_token_is_valid(oauth_type.clone(), token.clone(), user_id.clone()).await {
-        return make_json_response!(401, "Unauthorized");
+    if !oauth_token_is_valid(pool, oauth_type.clone(), token.clone(), user_id.clone()).await? {
+        return Err(AppError::Unauthorized);
     }
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
 
-    let user = match get_user_by_user_id(&user_id) {
-        Some(user) => user,
-        None => {
+    let user = match get_user_by_user_id(&connection, &user_id) {
+        Ok(user) => user,
+        Err(AppError::NotFound) => {
             info!("No user found with user_id {}", user_id);
-            return make_json_response!(401, "Unauthorized");
+            return Err(AppError::Unauthorized);
         }
+        Err(e) => return Err(e),
     };
 
     let video_id = id;
 
-    let mut video = match get_video_by_video_id(&video_id) {
-        Some(video) => video,
-        None => {
-            info!("No video found with video_id {}", video_id);
-            return make_json_response!(400, "Bad Request");
-        }
-    };
+    let mut video = get_video_by_video_id(&connection, &video_id)?;
 
     if video.owner_id != user.id {
         info!("User {} is not the owner of video {}", user.id, video.id);
-        return make_json_response!(401, "Unauthorized");
+        return Err(AppError::Forbidden);
     }
 
     if let Some(video_name) = &mut info.name {
@@ -402,25 +562,85 @@ pub async fn edit_video(
         video.video_desc = video_desc.clone();
     }
 
-    if let Some(shared_ids) = &mut info.share {
-        for id in shared_ids {
-            let user_share = match get_user_by_user_id(id) {
-                Some(u) => u,
+    if let Some(visibility) = info.visibility {
+        video.visibility = visibility.as_str().to_string();
+    }
+
+    if let Some(downloads_enabled) = info.downloads_enabled {
+        video.downloads_enabled = downloads_enabled;
+    }
+
+    if let Some(password) = &info.password {
+        if password.is_empty() {
+            video.password_hash = None;
+        } else {
+            video.password_hash = match hash_video_password(password) {
+                Some(password_hash) => Some(password_hash),
                 None => {
-                    info!("No user found with user_id {}", id);
-                    continue;
+                    return Err(AppError::Internal(String::from(
+                        "failed to hash video password",
+                    )))
                 }
             };
-            if user_share.id == user.id {
-                info!(
-                    "User {} is trying to share video {} with themselves",
-                    user.id, video.id
-                );
-                continue;
+        }
+    }
+
+    if let Some(folder) = &info.folder {
+        if folder.is_empty() {
+            video.folder_id = None;
+        } else {
+            match get_folder_by_folder_id(&connection, folder) {
+                Ok(folder) if folder.owner_id == user.id => video.folder_id = Some(folder.id),
+                Ok(folder) => {
+                    info!("User {} does not own folder {}", user.id, folder.id);
+                    return Err(AppError::Forbidden);
+                }
+                Err(AppError::NotFound) => return Err(AppError::BadRequest),
+                Err(e) => return Err(e),
             }
-            // TODO : Add share to `video_shares` table
         }
     }
 
-    make_json_response!(200, "Ok")
+    // Tags and shares are only changed along with the video
+    let video = connection.transaction::<_, AppError, _>(|| {
+        if let Some(tags) = &info.tags {
+            set_video_tags(&connection, video.id, video.owner_id, &normalize_tags(tags))?;
+        }
+
+        if let Some(shared_ids) = &info.share {
+            for id in shared_ids {
+                let user_share = match get_user_by_user_id(&connection, id) {
+                    Ok(u) => u,
+                    Err(AppError::NotFound) => {
+                        info!("No user found with user_id {}", id);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+                if user_share.id == user.id {
+                    info!(
+                        "User {} is trying to share video {} with themselves",
+                        user.id, video.id
+                    );
+                    continue;
+                }
+                insert_video_share(&connection, video.id, user_share.id)?;
+            }
+        }
+
+        update_video(
+            &connection,
+            video.id,
+            &VideoEdit {
+                video_name: video.video_name,
+                video_desc: video.video_desc,
+                visibility: video.visibility,
+                password_hash: video.password_hash,
+                folder_id: video.folder_id,
+                downloads_enabled: video.downloads_enabled,
+            },
+        )
+    })?;
+
+    Ok(ApiResponse::ok(video))
 }
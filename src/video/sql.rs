@@ -2,110 +2,395 @@ extern crate diesel;
 
 use std::path::PathBuf;
 
-use crate::{create_connection, models::*, util::make_random_string};
-use diesel::prelude::*;
+use crate::{
+    error::AppResult,
+    models::*,
+    util::make_random_string,
+    video::model::{VideoCursor, VideoListFilter, VideoSort, VideoStatus},
+};
+use diesel::{
+    dsl::now,
+    prelude::*,
+    sql_types::{Bool, Integer},
+};
 
-pub fn get_video_by_id(id: i32) -> Option<Video> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match crate::schema::videos::table
+// Defined in the folders_collections migration
+sql_function!(fn video_shared_with(video: Integer, viewer: Integer) -> Bool);
+
+pub fn get_video_by_id(connection: &PgConnection, id: i32) -> AppResult<Video> {
+    Ok(crate::schema::videos::table
         .filter(crate::schema::videos::dsl::id.eq(id.to_owned()))
-        .first::<Video>(&connection)
-    {
-        Ok(video) => Some(video),
-        Err(e) => {
-            info!("Failed to get video with id : {} (error {})", id, e);
-            None
-        }
-    }
+        .filter(crate::schema::videos::dsl::deleted_at.is_null())
+        .first::<Video>(connection)?)
 }
 
-pub fn get_video_by_video_id(id: &String) -> Option<Video> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match crate::schema::videos::table
+pub fn get_video_by_video_id(connection: &PgConnection, id: &String) -> AppResult<Video> {
+    Ok(crate::schema::videos::table
         .filter(crate::schema::videos::dsl::video_id.eq(id.to_owned()))
-        .first::<Video>(&connection)
-    {
-        Ok(video) => Some(video),
-        Err(e) => {
-            info!(
-                "Failed to get video with video id : {} (error {})",
-                id.to_owned(),
-                e
-            );
-            None
-        }
-    }
+        .filter(crate::schema::videos::dsl::deleted_at.is_null())
+        .first::<Video>(connection)?)
 }
 
-fn get_video_by_video_id_no_error(id: &String) -> Option<PathBuf> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match crate::schema::videos::table
+fn get_video_by_video_id_no_error(
+    connection: &PgConnection,
+    id: &String,
+) -> AppResult<Option<PathBuf>> {
+    Ok(crate::schema::videos::table
         .filter(crate::schema::videos::dsl::video_id.eq(id.to_owned()))
-        .first::<Video>(&connection)
-    {
-        Ok(video) => Some(PathBuf::from(video.video_path)),
-        Err(_) => None,
-    }
+        .first::<Video>(connection)
+        .optional()?
+        .map(|video| PathBuf::from(video.video_path)))
 }
 
-pub fn insert_new_video(video: &VideoNoId) -> Option<Video> {
-    let connection = create_connection().expect("Failed to connect to database");
-    match diesel::insert_into(crate::schema::videos::table)
-        .values(video)
-        .get_result::<Video>(&connection)
-    {
-        Ok(video) => Some(video),
-        Err(e) => {
-            info!(
-                "Failed to insert video with id : {} (error {})",
-                video.video_id, e
-            );
-            None
-        }
-    }
+pub fn insert_new_video(connection: &PgConnection, video: &VideoNoId) -> AppResult<Video> {
+    Ok(diesel::insert_into(crate::schema::videos::table)
+        .values((
+            video,
+            crate::schema::videos::dsl::created_at.eq(now),
+            crate::schema::videos::dsl::updated_at.eq(now),
+        ))
+        .get_result::<Video>(connection)?)
 }
 
-pub fn delete_video_with_video_id<T: Into<String>>(id: T) -> bool {
-    let connection = match crate::create_connection() {
-        Some(c) => c,
-        None => return false,
-    };
-    match diesel::delete(
+pub fn delete_video_with_video_id<T: Into<String>>(
+    connection: &PgConnection,
+    id: T,
+) -> AppResult<()> {
+    diesel::delete(
         crate::schema::videos::table.filter(crate::schema::videos::dsl::video_id.eq(id.into())),
     )
-    .execute(&connection)
-    {
-        Ok(_) => true,
-        Err(e) => {
-            info!("Failed to delete video from database with error {}", e);
-            false
-        }
-    }
+    .execute(connection)?;
+    Ok(())
 }
 
-pub fn delete_video_with_id<T: Into<i32>>(id: T) -> bool {
-    let connection = match crate::create_connection() {
-        Some(c) => c,
-        None => return false,
-    };
-    match diesel::delete(
+pub fn delete_video_with_id<T: Into<i32>>(connection: &PgConnection, id: T) -> AppResult<()> {
+    diesel::delete(
         crate::schema::videos::table.filter(crate::schema::videos::dsl::id.eq(id.into())),
     )
-    .execute(&connection)
-    {
-        Ok(_) => true,
-        Err(e) => {
-            info!("Failed to delete video from database with error {}", e);
-            false
-        }
-    }
+    .execute(connection)?;
+    Ok(())
 }
 
 /// Generates a new video id that does not exist in the database
-pub fn generate_new_video_id() -> String {
+pub fn generate_new_video_id(connection: &PgConnection) -> AppResult<String> {
     let mut video_id = make_random_string(32);
-    while let Some(_) = get_video_by_video_id_no_error(&video_id) {
+    while get_video_by_video_id_no_error(connection, &video_id)?.is_some() {
         video_id = make_random_string(32);
     }
-    video_id
+    Ok(video_id)
+}
+
+/// Saves an edited video. Fails with `NotFound` if it was moved to the trash meanwhile.
+pub fn update_video(connection: &PgConnection, id: i32, edit: &VideoEdit) -> AppResult<Video> {
+    Ok(diesel::update(
+        crate::schema::videos::table
+            .filter(crate::schema::videos::dsl::id.eq(id))
+            .filter(crate::schema::videos::dsl::deleted_at.is_null()),
+    )
+    .set(edit)
+    .get_result::<Video>(connection)?)
+}
+
+/// Records the file a video was (re)written to by a background job,
+/// leaving everything the owner can edit untouched. The caller has to hold a
+/// reference to `blob` for the video.
+pub fn update_video_media(
+    connection: &PgConnection,
+    id: i32,
+    blob: &MediaBlob,
+    video_url: &str,
+    video_length: f64,
+    status: VideoStatus,
+) -> AppResult<Video> {
+    use crate::schema::videos::dsl;
+
+    Ok(
+        diesel::update(crate::schema::videos::table.filter(dsl::id.eq(id)))
+            .set((
+                dsl::video_path.eq(&blob.path),
+                dsl::video_url.eq(video_url),
+                dsl::video_length.eq(video_length),
+                dsl::file_size.eq(blob.file_size),
+                dsl::status.eq(status.as_str()),
+                dsl::sha256.eq(&blob.sha256),
+            ))
+            .get_result::<Video>(connection)?,
+    )
+}
+
+pub fn set_video_status(connection: &PgConnection, id: i32, status: VideoStatus) -> AppResult<()> {
+    diesel::update(crate::schema::videos::table.filter(crate::schema::videos::dsl::id.eq(id)))
+        .set(crate::schema::videos::dsl::status.eq(status.as_str()))
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn set_video_length(connection: &PgConnection, id: i32, video_length: f64) -> AppResult<()> {
+    diesel::update(crate::schema::videos::table.filter(crate::schema::videos::dsl::id.eq(id)))
+        .set(crate::schema::videos::dsl::video_length.eq(video_length))
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn set_video_thumbnail(
+    connection: &PgConnection,
+    id: i32,
+    thumbnail_path: &str,
+    thumbnail_sha256: &str,
+) -> AppResult<()> {
+    use crate::schema::videos::dsl;
+
+    diesel::update(crate::schema::videos::table.filter(dsl::id.eq(id)))
+        .set((
+            dsl::thumbnail_path.eq(thumbnail_path),
+            dsl::thumbnail_sha256.eq(thumbnail_sha256),
+        ))
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn insert_video_share(connection: &PgConnection, video_id: i32, user_id: i32) -> AppResult<()> {
+    diesel::insert_into(crate::schema::video_shares::table)
+        .values(&VideoShareNoId { video_id, user_id })
+        .on_conflict_do_nothing()
+        .execute(connection)?;
+    Ok(())
+}
+
+/// Whether a video is shared with a user directly or through one of its folders
+pub fn video_is_shared_with_user(
+    connection: &PgConnection,
+    video_id: i32,
+    user_id: i32,
+) -> AppResult<bool> {
+    Ok(diesel::select(video_shared_with(video_id, user_id)).get_result::<bool>(connection)?)
+}
+
+/// Applies keyset pagination on `$column` (with the video id as a tie breaker)
+/// to a boxed videos query, then orders it by the same columns.
+macro_rules! paginate_videos_by {
+    ($query:expr, $column:expr, $cursor:expr, $descending:expr) => {{
+        use crate::schema::videos::dsl::id as video_table_id;
+        let mut query = $query;
+        if let Some((value, last_id)) = $cursor {
+            query = if $descending {
+                query.filter(
+                    $column
+                        .lt(value.clone())
+                        .or($column.eq(value).and(video_table_id.lt(last_id))),
+                )
+            } else {
+                query.filter(
+                    $column
+                        .gt(value.clone())
+                        .or($column.eq(value).and(video_table_id.gt(last_id))),
+                )
+            };
+        }
+        if $descending {
+            query.order(($column.desc(), video_table_id.desc()))
+        } else {
+            query.order(($column.asc(), video_table_id.asc()))
+        }
+    }};
+}
+
+/// Gets a page of the videos owned by or shared with a user.
+/// Fetches one more video than the limit so callers can tell if there is a next page.
+pub fn get_videos_for_user(
+    connection: &PgConnection,
+    user_id: i32,
+    filter: &VideoListFilter,
+) -> AppResult<Vec<Video>> {
+    use crate::schema::videos::dsl;
+
+    let mut query = crate::schema::videos::table
+        .filter(
+            dsl::owner_id
+                .eq(user_id)
+                .or(video_shared_with(dsl::id, user_id)),
+        )
+        .filter(dsl::deleted_at.is_null())
+        .into_boxed();
+
+    if let Some(name) = &filter.name {
+        let escaped = name
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        query = query.filter(dsl::video_name.ilike(format!("%{}%", escaped)));
+    }
+    if let Some(min_duration) = filter.min_duration {
+        query = query.filter(dsl::video_length.ge(min_duration));
+    }
+    if let Some(max_duration) = filter.max_duration {
+        query = query.filter(dsl::video_length.le(max_duration));
+    }
+    if let Some(status) = filter.status {
+        query = query.filter(dsl::status.eq(status.as_str()));
+    }
+    if let Some(tag) = &filter.tag {
+        use crate::schema::{tags, video_tags};
+        let tagged_video_ids = video_tags::table
+            .inner_join(tags::table)
+            .filter(tags::dsl::name.eq(tag.clone()))
+            .select(video_tags::dsl::video_id);
+        query = query.filter(dsl::id.eq_any(tagged_video_ids));
+    }
+
+    let cursor = filter.cursor.clone();
+    let query = match filter.sort {
+        VideoSort::Date => {
+            let cursor = match cursor {
+                Some(VideoCursor::Date(created_at, id)) => Some((created_at, id)),
+                _ => None,
+            };
+            paginate_videos_by!(query, dsl::created_at, cursor, filter.descending)
+        }
+        VideoSort::Name => {
+            let cursor = match cursor {
+                Some(VideoCursor::Name(name, id)) => Some((name, id)),
+                _ => None,
+            };
+            paginate_videos_by!(query, dsl::video_name, cursor, filter.descending)
+        }
+        VideoSort::Duration => {
+            let cursor = match cursor {
+                Some(VideoCursor::Duration(length, id)) => Some((length, id)),
+                _ => None,
+            };
+            paginate_videos_by!(query, dsl::video_length, cursor, filter.descending)
+        }
+        VideoSort::Size => {
+            let cursor = match cursor {
+                Some(VideoCursor::Size(size, id)) => Some((size, id)),
+                _ => None,
+            };
+            paginate_videos_by!(query, dsl::file_size, cursor, filter.descending)
+        }
+    };
+
+    Ok(query.limit(filter.limit + 1).load::<Video>(connection)?)
+}
+
+/// Sets the sizes of videos uploaded before they were tracked from their file.
+/// Videos are only backfilled once.
+pub fn backfill_video_file_sizes(connection: &PgConnection) -> AppResult<()> {
+    use crate::schema::{video_file_size_backfill, videos};
+
+    let pending = video_file_size_backfill::table
+        .inner_join(videos::table)
+        .select((videos::dsl::id, videos::dsl::video_path))
+        .load::<(i32, String)>(connection)?;
+
+    for (id, video_path) in pending {
+        match std::fs::metadata(&video_path) {
+            Ok(metadata) => {
+                if let Err(e) = diesel::update(videos::table.filter(videos::dsl::id.eq(id)))
+                    .set(videos::dsl::file_size.eq(metadata.len() as i64))
+                    .execute(connection)
+                {
+                    warn!("Failed to backfill size for video {} (error {})", id, e);
+                    continue;
+                }
+            }
+            Err(e) => warn!(
+                "Failed to read size of {} for video {} (error {})",
+                video_path, id, e
+            ),
+        }
+        if let Err(e) = diesel::delete(
+            video_file_size_backfill::table.filter(video_file_size_backfill::dsl::video_id.eq(id)),
+        )
+        .execute(connection)
+        {
+            warn!("Failed to mark video {} as backfilled (error {})", id, e);
+        }
+    }
+    Ok(())
+}
+
+/// Gets the id and file of every video without a hash, which were stored before
+/// uploads were deduplicated or clipped before clips were. Processing videos are
+/// left out, their file may not be in place yet.
+pub fn get_unhashed_videos(connection: &PgConnection) -> AppResult<Vec<(i32, String)>> {
+    use crate::schema::videos::dsl;
+
+    Ok(crate::schema::videos::table
+        .filter(dsl::sha256.is_null())
+        .filter(dsl::status.ne(VideoStatus::Processing.as_str()))
+        .order(dsl::id.asc())
+        .select((dsl::id, dsl::video_path))
+        .load::<(i32, String)>(connection)?)
+}
+
+/// Points a video without a hash at `blob`, returning whether it still had no hash.
+/// The caller has to hold a reference to `blob` for the video.
+pub fn set_unhashed_video_blob(
+    connection: &PgConnection,
+    id: i32,
+    blob: &MediaBlob,
+) -> AppResult<bool> {
+    use crate::schema::videos::dsl;
+
+    let updated = diesel::update(
+        crate::schema::videos::table
+            .filter(dsl::id.eq(id))
+            .filter(dsl::sha256.is_null()),
+    )
+    .set((dsl::video_path.eq(&blob.path), dsl::sha256.eq(&blob.sha256)))
+    .execute(connection)?;
+    Ok(updated > 0)
+}
+
+/// Sets the timestamps of videos uploaded before they were tracked from their
+/// file's modification time. Videos are only backfilled once.
+pub fn backfill_video_timestamps(connection: &PgConnection) -> AppResult<()> {
+    use crate::schema::{video_timestamp_backfill, videos};
+
+    let pending = video_timestamp_backfill::table
+        .inner_join(videos::table)
+        .select((videos::dsl::id, videos::dsl::video_path))
+        .load::<(i32, String)>(connection)?;
+
+    for (id, video_path) in pending {
+        match std::fs::metadata(&video_path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => {
+                let modified = chrono::DateTime::<chrono::Utc>::from(modified).naive_utc();
+                if let Err(e) = diesel::update(videos::table.filter(videos::dsl::id.eq(id)))
+                    .set((
+                        videos::dsl::created_at.eq(modified),
+                        videos::dsl::updated_at.eq(modified),
+                    ))
+                    .execute(connection)
+                {
+                    warn!(
+                        "Failed to backfill timestamps for video {} (error {})",
+                        id, e
+                    );
+                    continue;
+                }
+            }
+            Err(e) => warn!(
+                "Failed to read modification time of {} for video {} (error {})",
+                video_path, id, e
+            ),
+        }
+        if let Err(e) = diesel::delete(
+            video_timestamp_backfill::table.filter(video_timestamp_backfill::dsl::video_id.eq(id)),
+        )
+        .execute(connection)
+        {
+            warn!("Failed to mark video {} as backfilled (error {})", id, e);
+        }
+    }
+    Ok(())
+}
+
+pub fn get_videos_in_folder(connection: &PgConnection, folder_id: i32) -> AppResult<Vec<Video>> {
+    Ok(crate::schema::videos::table
+        .filter(crate::schema::videos::dsl::folder_id.eq(folder_id))
+        .filter(crate::schema::videos::dsl::deleted_at.is_null())
+        .order(crate::schema::videos::dsl::video_name.asc())
+        .load::<Video>(connection)?)
 }
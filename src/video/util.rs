@@ -1,4 +1,27 @@
-const ENDINGS: [&'static str; 11] = [
+use crate::{
+    auth::util::user_has_admin_permission,
+    db::{get_connection, DbPool},
+    error::{AppError, AppResult},
+    event::{model::VideoEventKind, util::VideoEvents},
+    job::{model::JobKind, util::enqueue_job},
+    media::{sql::reference_media_blob, util::media_blob_path},
+    models::{MediaBlobNoId, User, Video, VideoNoId},
+    tag::sql::set_video_tags,
+    video::{
+        model::{VideoStatus, Visibility},
+        password::has_valid_video_grant,
+        sql::{
+            get_video_by_id, insert_new_video, set_video_length, set_video_status,
+            video_is_shared_with_user,
+        },
+    },
+};
+use diesel::{Connection, PgConnection};
+use rocket::http::CookieJar;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+const ENDINGS: [&str; 11] = [
     "mp4", "mkv", "avi", "mov", "wmv", "flv", "mpg", "mpeg", "m4v", "3gp", "webm",
 ];
 
@@ -21,3 +44,342 @@ pub fn get_filename_ending<T: Into<String>>(filename: T) -> Option<String> {
         None
     }
 }
+
+/// Replaces the ending of `filename`, adding one if it has none
+pub fn with_filename_ending(filename: &str, ending: &str) -> String {
+    match filename.rsplit_once('.') {
+        Some((stem, _)) => format!("{}.{}", stem, ending),
+        None => format!("{}.{}", filename, ending),
+    }
+}
+
+/// Whether `timestamp` is a valid playback time for a video of `video_length` seconds.
+/// Videos with an unknown length (negative) accept any non negative time.
+pub fn valid_playback_time(timestamp: f64, video_length: f64) -> bool {
+    timestamp.is_finite() && timestamp >= 0.0 && (video_length < 0.0 || timestamp <= video_length)
+}
+
+/// Markers in the names of files that are still being written by an upload or clip job,
+/// which only get moved to the video's path once they are complete
+pub const TEMP_FILE_MARKERS: [&str; 2] = [".upload.", ".clip."];
+
+/// Where an upload is written until its video is in the database
+pub fn upload_temp_path(folder: &str, video_id: &str, ending: &str) -> String {
+    format!("{}/{}.upload.{}", folder, video_id, ending)
+}
+
+/// Keeps the name of an uploaded file as the user had it, minus any directories
+/// and control characters, for use as the download filename.
+pub fn clean_original_filename(filename: &str) -> String {
+    let filename = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let mut filename: String = filename.chars().filter(|c| !c.is_control()).collect();
+    if filename.len() > 255 {
+        let mut end = 255;
+        while !filename.is_char_boundary(end) {
+            end -= 1;
+        }
+        filename.truncate(end);
+    }
+    filename
+}
+
+/// Inserts the video of an upload whose content hashed to `video.sha256`, pointing
+/// it at the stored file with that content, and queues it to be probed. Uploads of
+/// content that is already stored share its file.
+pub fn insert_upload(
+    connection: &PgConnection,
+    video: &mut VideoNoId,
+    ending: &str,
+    tags: Option<&[String]>,
+) -> AppResult<Video> {
+    let sha256 = match &video.sha256 {
+        Some(sha256) => sha256.clone(),
+        None => {
+            return Err(AppError::Internal(String::from(
+                "an upload has to be hashed before it is stored",
+            )))
+        }
+    };
+    connection.transaction::<_, AppError, _>(|| {
+        let blob = reference_media_blob(
+            connection,
+            &MediaBlobNoId {
+                path: media_blob_path(&sha256, ending),
+                sha256,
+                file_size: video.file_size,
+                ref_count: 1,
+            },
+        )?;
+        video.video_path = blob.path;
+        let inserted = insert_new_video(connection, video)?;
+        if let Some(tags) = tags {
+            set_video_tags(connection, inserted.id, inserted.owner_id, tags)?;
+        }
+        // Probing and everything after it reads the whole file, so don't make the
+        // upload wait for it
+        enqueue_job(
+            connection,
+            &JobKind::ProbeVideo {
+                video_id: inserted.id,
+            },
+        )?;
+        Ok(inserted)
+    })
+}
+
+/// Runs ffprobe on a blocking thread, it waits for the process to exit
+pub async fn probe_media(path: &str) -> AppResult<ffprobe::FfProbe> {
+    let path = path.to_string();
+    match rocket::tokio::task::spawn_blocking(move || ffprobe::ffprobe(&path)).await {
+        Ok(Ok(probe)) => Ok(probe),
+        Ok(Err(e)) => Err(AppError::Probe(e.to_string())),
+        Err(e) => Err(AppError::Internal(format!("ffprobe panicked ({})", e))),
+    }
+}
+
+/// Reads the length in seconds of a probed video, or -1.0 if it is unknown
+pub fn probed_video_length(probe: &ffprobe::FfProbe) -> f64 {
+    // Files without streams or a stream duration can still have one for the container
+    let duration = match probe
+        .streams
+        .first()
+        .and_then(|stream| stream.duration.as_ref())
+        .or(probe.format.duration.as_ref())
+    {
+        Some(duration) => duration,
+        None => {
+            warn!("Failed to get duration from video");
+            return -1.0;
+        }
+    };
+    match duration.parse::<f64>() {
+        Ok(duration) => duration,
+        Err(e) => {
+            warn!(
+                "Failed to parse duration {} from video with error: {}",
+                duration, e
+            );
+            -1.0
+        }
+    }
+}
+
+/// Reads the length in seconds of the video at `path`, or -1.0 if it is unknown
+pub async fn probe_video_length(path: &str) -> f64 {
+    match probe_media(path).await {
+        Ok(probe) => probed_video_length(&probe),
+        Err(e) => {
+            warn!("Failed to probe for video length ({})", e);
+            -1.0
+        }
+    }
+}
+
+/// Reads the length of an uploaded video and marks it as ready, then queues the
+/// imports and previews of its file
+pub async fn probe_upload(pool: &DbPool, events: &VideoEvents, id: i32) -> AppResult<()> {
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    let video = match get_video_by_id(&connection, id) {
+        Ok(video) => video,
+        // Deleted before it was probed
+        Err(AppError::NotFound) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    drop(connection);
+
+    // The upload is moved into place right after its video is committed
+    if !std::path::Path::new(&video.video_path).exists() {
+        return Err(AppError::Internal(format!(
+            "file {} of video {} is not in place yet",
+            video.video_path, video.video_id
+        )));
+    }
+    // A file ffprobe can not read is not a video, which fails the job until it is dead
+    let probe = probe_media(&video.video_path).await?;
+    let video_length = probed_video_length(&probe);
+
+    let connection = get_connection(pool)
+        .await
+        .ok_or(AppError::ServiceUnavailable)?;
+    connection.transaction::<_, AppError, _>(|| {
+        set_video_length(&connection, video.id, video_length)?;
+        set_video_status(&connection, video.id, VideoStatus::Ready)?;
+        enqueue_job(&connection, &JobKind::ImportMetadata { video_id: video.id })?;
+        enqueue_job(
+            &connection,
+            &JobKind::GeneratePreviews { video_id: video.id },
+        )?;
+        #[cfg(feature = "transcription")]
+        enqueue_job(&connection, &JobKind::Transcribe { video_id: video.id })?;
+        Ok(())
+    })?;
+    events.publish(&video, VideoEventKind::ProbeDone { video_length });
+    events.publish(&video, VideoEventKind::Ready);
+    Ok(())
+}
+
+/// Marks an upload that could not be probed as failed, it would stay processing otherwise
+pub fn fail_upload(connection: &PgConnection, events: &VideoEvents, id: i32) -> AppResult<()> {
+    let video = match get_video_by_id(connection, id) {
+        Ok(video) => video,
+        Err(AppError::NotFound) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    set_video_status(connection, video.id, VideoStatus::Failed)?;
+    events.publish(
+        &video,
+        VideoEventKind::Failed {
+            reason: String::from("the upload could not be processed"),
+        },
+    );
+    Ok(())
+}
+
+/// The result of checking a request's access to a video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoAccess {
+    Allowed,
+    /// The viewer could watch the video but has to unlock it with its password first
+    PasswordRequired,
+    Denied,
+}
+
+/// Owners, admins and users the video is shared with can always see a video,
+/// regardless of its visibility or password.
+pub fn user_has_video_privileges(
+    connection: &PgConnection,
+    video: &Video,
+    user: Option<&User>,
+) -> AppResult<bool> {
+    Ok(match user {
+        Some(user) => {
+            video.owner_id == user.id
+                || user_has_admin_permission(user)
+                || video_is_shared_with_user(connection, video.id, user.id)?
+        }
+        None => false,
+    })
+}
+
+/// Checks whether `user` (None for anonymous requests) may view `video`,
+/// based on ownership, shares and the video's visibility.
+pub fn user_can_view_video(
+    connection: &PgConnection,
+    video: &Video,
+    user: Option<&User>,
+) -> AppResult<bool> {
+    Ok(user_has_video_privileges(connection, video, user)? || visibility_allows_user(video, user))
+}
+
+fn visibility_allows_user(video: &Video, user: Option<&User>) -> bool {
+    match video.visibility.parse::<Visibility>() {
+        Ok(Visibility::Private) => false,
+        Ok(Visibility::Unlisted) | Ok(Visibility::Public) => true,
+        Ok(Visibility::Internal) => user.is_some(),
+        Err(_) => {
+            warn!(
+                "Video {} has unknown visibility {}",
+                video.video_id, video.visibility
+            );
+            false
+        }
+    }
+}
+
+/// Like `user_can_view_video`, but also requires a grant cookie for password
+/// protected videos unless the user is the owner, an admin or shared the video.
+pub fn get_video_access(
+    connection: &PgConnection,
+    video: &Video,
+    user: Option<&User>,
+    cookies: &CookieJar<'_>,
+) -> AppResult<VideoAccess> {
+    if user_has_video_privileges(connection, video, user)? {
+        return Ok(VideoAccess::Allowed);
+    }
+
+    if !visibility_allows_user(video, user) {
+        return Ok(VideoAccess::Denied);
+    }
+
+    if video.password_hash.is_some() && !has_valid_video_grant(cookies, &video.video_id) {
+        return Ok(VideoAccess::PasswordRequired);
+    }
+
+    Ok(VideoAccess::Allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::insert_upload;
+    use crate::{
+        auth::sql::insert_user,
+        media::sql::get_duplicate_video_ids,
+        models::{MediaBlob, VideoNoId},
+        video::{model::VideoStatus, sql::generate_new_video_id},
+    };
+    use diesel::{prelude::*, PgConnection};
+
+    fn upload(connection: &PgConnection, owner_id: i32, sha256: &str) -> VideoNoId {
+        let video_id = generate_new_video_id(connection).unwrap();
+        VideoNoId {
+            video_url: format!("/api/video/{}/cat.mp4", video_id),
+            video_id,
+            video_path: String::new(),
+            video_name: String::from("cat.mp4"),
+            video_length: -1.0,
+            video_desc: String::new(),
+            owner_id,
+            thumbnail_path: None,
+            visibility: String::from("private"),
+            password_hash: None,
+            file_size: 1024,
+            status: VideoStatus::Processing.as_str().to_string(),
+            folder_id: None,
+            source_video_id: None,
+            original_filename: String::from("cat.mp4"),
+            downloads_enabled: true,
+            sha256: Some(sha256.to_string()),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a PostgreSQL database at DATABASE_URL"]
+    fn same_content_uploaded_twice_shares_one_file() {
+        dotenv::dotenv().ok();
+        let connection = PgConnection::establish(&std::env::var("DATABASE_URL").unwrap()).unwrap();
+        connection.begin_test_transaction().unwrap();
+        crate::embedded_migrations::run(&connection).unwrap();
+
+        let user = insert_user(&connection, String::from("dedup@example.com")).unwrap();
+        let sha256 = "c0ffee".repeat(10);
+        let first = insert_upload(
+            &connection,
+            &mut upload(&connection, user.id, &sha256),
+            "mp4",
+            None,
+        )
+        .unwrap();
+        let second = insert_upload(
+            &connection,
+            &mut upload(&connection, user.id, &sha256),
+            "mp4",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(first.video_path, second.video_path);
+        assert_eq!(
+            get_duplicate_video_ids(&connection, user.id, &sha256, second.id).unwrap(),
+            vec![first.video_id]
+        );
+        let blob = crate::schema::media_blobs::table
+            .filter(crate::schema::media_blobs::dsl::sha256.eq(&sha256))
+            .first::<MediaBlob>(&connection)
+            .unwrap();
+        assert_eq!(blob.ref_count, 2);
+    }
+}